@@ -0,0 +1,82 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use nih_plug::prelude::AuxiliaryBuffers;
+use std::f32::consts;
+use superclick::bench::{new_buffer, ProcessBench};
+
+const SAMPLE_RATES: &[f32] = &[44100f32, 48000f32, 96000f32, 192000f32];
+const BUFFER_SIZES: &[usize] = &[16, 64, 256, 1024, 4096];
+const CHANNEL_COUNTS: &[usize] = &[1, 2, 4];
+const AUX_OUTPUT_COUNT: usize = 3;
+
+/// Frequency and level of the sidechain signal, which is loud enough to
+/// duck the clicks
+const SIDECHAIN_FREQUENCY: f32 = 440f32;
+const SIDECHAIN_LEVEL: f32 = 0.5f32;
+
+/// Cost of processing a block with the default parameters and ducking
+/// against a sidechain: throughput is reported in samples so that the cost
+/// per sample can be compared across buffer sizes
+fn process(c: &mut Criterion) {
+    let mut group = c.benchmark_group("process");
+    for &sample_rate in SAMPLE_RATES {
+        for &channel_count in CHANNEL_COUNTS {
+            for &buffer_size in BUFFER_SIZES {
+                let mut bench = ProcessBench::new(sample_rate, channel_count, buffer_size);
+                let mut main = vec![vec![0f32; buffer_size]; channel_count];
+                let sidechain_channel = (0..buffer_size)
+                    .map(|index| {
+                        let t = index as f32 / sample_rate;
+                        SIDECHAIN_LEVEL * (consts::TAU * SIDECHAIN_FREQUENCY * t).sin()
+                    })
+                    .collect::<Vec<_>>();
+                let mut sidechain = vec![sidechain_channel; channel_count];
+                let mut aux_outputs =
+                    vec![vec![vec![0f32; buffer_size]; channel_count]; AUX_OUTPUT_COUNT];
+                let mut buffer = new_buffer(&mut main, buffer_size);
+                let mut aux_inputs = [new_buffer(&mut sidechain, buffer_size)];
+                let mut aux_buffers = aux_outputs
+                    .iter_mut()
+                    .map(|channels| new_buffer(channels, buffer_size))
+                    .collect::<Vec<_>>();
+                let mut aux = AuxiliaryBuffers {
+                    inputs: &mut aux_inputs,
+                    outputs: &mut aux_buffers,
+                };
+
+                group.throughput(Throughput::Elements(buffer_size as u64));
+                group.bench_function(
+                    BenchmarkId::new(
+                        format!("{} Hz/{} channels", sample_rate, channel_count),
+                        buffer_size,
+                    ),
+                    |b| b.iter(|| bench.process(&mut buffer, &mut aux)),
+                );
+            }
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, process);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use superclick::bench::{BeatBench, ToneBench};
+
+const SAMPLE_RATE: f32 = 48000f32;
+const BUFFER_SIZES: &[usize] = &[16, 32, 64, 128, 256, 512, 1024];
+const CHANNEL_COUNT: usize = 2;
+
+fn render_tone(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render_tone");
+    for &buffer_size in BUFFER_SIZES {
+        let mut bench = ToneBench::new(SAMPLE_RATE, buffer_size, CHANNEL_COUNT);
+        let mut channels = vec![vec![0f32; buffer_size]; CHANNEL_COUNT];
+        let mut channels = channels
+            .iter_mut()
+            .map(|channel| channel.as_mut_slice())
+            .collect::<Vec<_>>();
+        group.throughput(Throughput::Elements(buffer_size as u64));
+        group.bench_function(BenchmarkId::from_parameter(buffer_size), |b| {
+            b.iter(|| bench.render(&mut channels))
+        });
+    }
+    group.finish();
+}
+
+/// Cost of finding where each beat starts and stops by checking the beat at
+/// every sample compared with finding it from the tempo once per beat
+fn find_beats(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_beats");
+    for &buffer_size in BUFFER_SIZES {
+        let mut bench = BeatBench::new(SAMPLE_RATE);
+        group.throughput(Throughput::Elements(buffer_size as u64));
+        group.bench_function(BenchmarkId::new("per sample", buffer_size), |b| {
+            b.iter(|| bench.per_sample(buffer_size))
+        });
+        group.bench_function(BenchmarkId::new("block", buffer_size), |b| {
+            b.iter(|| bench.block(buffer_size))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render_tone, find_beats);
+criterion_main!(benches);
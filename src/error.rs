@@ -19,14 +19,84 @@
 // OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
+use crate::superclick::Tier;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
 #[derive(Debug)]
 pub enum Error {
-    TempoUnavailable = 0x1001,
-    BarNumberUnavailable = 0x1002,
-    BarStartPosBeatsUnavailable = 0x1003,
-    PosBeatsUnavailable = 0x1004,
-    TimeSigNumeratorUnavailable = 0x1005,
-    TimeSignDenominatorUnavailable = 0x1006,
-    InvalidTimeSignatureTop = 0x1007,
-    InvalidTimeSignatureBottom = 0x1008,
+    TempoUnavailable,
+    BarNumberUnavailable,
+    BarStartPosBeatsUnavailable,
+    PosBeatsUnavailable,
+    TimeSigNumeratorUnavailable,
+    TimeSignDenominatorUnavailable,
+    InvalidTimeSignatureTop,
+    InvalidTimeSignatureBottom,
+    InvalidGrouping,
+    ProfileReadFailed,
+    /// Message from the TOML or JSON parser
+    ProfileParseFailed(String),
+    UnsupportedProfileFormat,
+    InvalidProfileName,
+    /// Invalid field in the given tier of a profile
+    InvalidProfileFrequency(Tier),
+    InvalidProfileLength(Tier),
+    InvalidProfileLevel(Tier),
+    PresetDirUnavailable,
+    PresetReadFailed,
+    PresetWriteFailed,
+    PresetParseFailed,
+    InvalidPresetName,
+    PresetAlreadyExists,
+    SetlistReadFailed,
+    SetlistParseFailed,
+    InvalidSongName,
+    InvalidSongTempo,
+    InvalidSongCountIn,
+    SampleReadFailed,
+    InvalidCueBar,
+    InvalidCueLabel,
+}
+
+impl Error {
+    pub fn code(&self) -> isize {
+        match self {
+            Self::TempoUnavailable => 0x1001,
+            Self::BarNumberUnavailable => 0x1002,
+            Self::BarStartPosBeatsUnavailable => 0x1003,
+            Self::PosBeatsUnavailable => 0x1004,
+            Self::TimeSigNumeratorUnavailable => 0x1005,
+            Self::TimeSignDenominatorUnavailable => 0x1006,
+            Self::InvalidTimeSignatureTop => 0x1007,
+            Self::InvalidTimeSignatureBottom => 0x1008,
+            Self::InvalidGrouping => 0x1009,
+            Self::ProfileReadFailed => 0x2001,
+            Self::ProfileParseFailed(_) => 0x2002,
+            Self::UnsupportedProfileFormat => 0x2003,
+            Self::InvalidProfileName => 0x2004,
+            Self::InvalidProfileFrequency(_) => 0x2005,
+            Self::InvalidProfileLength(_) => 0x2006,
+            Self::InvalidProfileLevel(_) => 0x2007,
+            Self::PresetDirUnavailable => 0x3001,
+            Self::PresetReadFailed => 0x3002,
+            Self::PresetWriteFailed => 0x3003,
+            Self::PresetParseFailed => 0x3004,
+            Self::InvalidPresetName => 0x3005,
+            Self::PresetAlreadyExists => 0x3006,
+            Self::SetlistReadFailed => 0x4001,
+            Self::SetlistParseFailed => 0x4002,
+            Self::InvalidSongName => 0x4003,
+            Self::InvalidSongTempo => 0x4004,
+            Self::InvalidSongCountIn => 0x4005,
+            Self::SampleReadFailed => 0x5001,
+            Self::InvalidCueBar => 0x5002,
+            Self::InvalidCueLabel => 0x5003,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{:?}", self)
+    }
 }
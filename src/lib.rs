@@ -26,6 +26,15 @@ mod result;
 mod superclick;
 
 use nih_plug::nih_export_clap;
-use superclick::SuperClick;
+#[cfg(feature = "vst3")]
+use nih_plug::nih_export_vst3;
+
+pub use superclick::SuperClick;
+
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub use superclick::bench;
 
 nih_export_clap!(SuperClick);
+#[cfg(feature = "vst3")]
+nih_export_vst3!(SuperClick);
@@ -0,0 +1,120 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::{Grouping, TimeSignatureBottom, TimeSignatureTop};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Note value counted as one beat when expressing a tempo
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BeatUnit {
+    Half,
+    DottedHalf,
+    Quarter,
+    DottedQuarter,
+    Eighth,
+    DottedEighth,
+    Sixteenth,
+
+    /// One beat per note of the time signature's bottom number (e.g. a
+    /// triplet minim in 4/3)
+    Pulse,
+
+    /// One beat per group of the bar's grouping (e.g. three beats per bar of
+    /// 7/8 grouped 2+2+3)
+    Grouped,
+}
+
+impl BeatUnit {
+    /// Conventional beat unit for a meter: one beat per group for meters
+    /// with an explicit grouping, dotted beats for compound meters and the
+    /// time signature's bottom number otherwise
+    pub fn for_meter(
+        time_signature_top: TimeSignatureTop,
+        time_signature_bottom: TimeSignatureBottom,
+        grouping: Option<Grouping>,
+    ) -> Self {
+        if grouping.is_some() {
+            return Self::Grouped;
+        }
+
+        let is_compound = time_signature_top.basis() == 3;
+        match (time_signature_bottom.as_number(), is_compound) {
+            (2, _) => Self::Half,
+            (4, false) => Self::Quarter,
+            (4, true) => Self::DottedHalf,
+            (8, false) => Self::Eighth,
+            (8, true) => Self::DottedQuarter,
+            (16, false) => Self::Sixteenth,
+            (16, true) => Self::DottedEighth,
+            _ => Self::Pulse,
+        }
+    }
+
+    /// Length of one beat in crotchets: grouped beats have the average
+    /// length of the bar's groups
+    pub fn length_crotchets(
+        &self,
+        time_signature_top: TimeSignatureTop,
+        time_signature_bottom: TimeSignatureBottom,
+        grouping: Option<Grouping>,
+    ) -> f64 {
+        match self {
+            Self::Half => 2f64,
+            Self::DottedHalf => 3f64,
+            Self::Quarter => 1f64,
+            Self::DottedQuarter => 1.5f64,
+            Self::Eighth => 0.5f64,
+            Self::DottedEighth => 0.75f64,
+            Self::Sixteenth => 0.25f64,
+            Self::Pulse => 4f64 / time_signature_bottom.as_number() as f64,
+            Self::Grouped => {
+                let pulse_crotchets = 4f64 / time_signature_bottom.as_number() as f64;
+                match grouping {
+                    Some(grouping) => {
+                        grouping.beat_count() as f64 * pulse_crotchets
+                            / grouping.group_count() as f64
+                    }
+                    None => time_signature_top.basis() as f64 * pulse_crotchets,
+                }
+            }
+        }
+    }
+}
+
+impl Display for BeatUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Half => "half",
+                Self::DottedHalf => "dotted half",
+                Self::Quarter => "quarter",
+                Self::DottedQuarter => "dotted quarter",
+                Self::Eighth => "eighth",
+                Self::DottedEighth => "dotted eighth",
+                Self::Sixteenth => "sixteenth",
+                Self::Pulse => "pulse",
+                Self::Grouped => "group",
+            }
+        )
+    }
+}
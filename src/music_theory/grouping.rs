@@ -0,0 +1,176 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+const MAX_GROUPS: usize = 8;
+
+/// Grouping of the beats of a bar into accented groups (e.g. 2+2+3)
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Grouping {
+    groups: [u8; MAX_GROUPS],
+    len: usize,
+}
+
+impl Grouping {
+    /// Total number of beats in all groups
+    pub fn beat_count(&self) -> i32 {
+        self.groups().iter().map(|&g| g as i32).sum()
+    }
+
+    /// Number of groups
+    pub fn group_count(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the beat with the given zero-based index starts a group
+    pub fn is_group_start(&self, index: i32) -> bool {
+        let mut start = 0;
+        for &group in self.groups() {
+            if index == start {
+                return true;
+            }
+            start += group as i32;
+        }
+        false
+    }
+
+    /// Packs the grouping into an integer for storage in an atomic: zero
+    /// is never a valid grouping
+    pub fn to_bits(&self) -> u64 {
+        self.groups
+            .iter()
+            .enumerate()
+            .fold(0u64, |bits, (i, &group)| bits | ((group as u64) << (i * 8)))
+    }
+
+    pub fn from_bits(bits: u64) -> Option<Self> {
+        let mut groups = [0u8; MAX_GROUPS];
+        let mut len = 0;
+        for (i, group) in groups.iter_mut().enumerate() {
+            *group = (bits >> (i * 8)) as u8;
+            if *group != 0 {
+                len = i + 1;
+            }
+        }
+        if len == 0 {
+            None
+        } else {
+            Some(Self { groups, len })
+        }
+    }
+
+    /// Number of beats in each group
+    pub fn groups(&self) -> &[u8] {
+        &self.groups[..self.len]
+    }
+}
+
+impl FromStr for Grouping {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut groups = [0u8; MAX_GROUPS];
+        let mut len = 0;
+        for part in s.split('+') {
+            if len == MAX_GROUPS {
+                return Err(Error::InvalidGrouping);
+            }
+            groups[len] = match part.trim().parse::<u8>() {
+                Ok(group) if group >= 1 => group,
+                _ => return Err(Error::InvalidGrouping),
+            };
+            len += 1;
+        }
+        Ok(Self { groups, len })
+    }
+}
+
+impl TryFrom<String> for Grouping {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Grouping> for String {
+    fn from(value: Grouping) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for Grouping {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (i, group) in self.groups().iter().enumerate() {
+            if i > 0 {
+                write!(f, "+")?;
+            }
+            write!(f, "{}", group)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Grouping;
+
+    #[test]
+    fn parse() {
+        let grouping = "2+2+3".parse::<Grouping>().unwrap();
+        assert_eq!(&[2, 2, 3], grouping.groups());
+        assert_eq!(7, grouping.beat_count());
+        assert_eq!(3, grouping.group_count());
+        assert_eq!("2+2+3", grouping.to_string());
+        assert_eq!(grouping, " 2 + 2 + 3 ".parse().unwrap());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for s in ["", "2+", "0+3", "2+x", "-1", "1+1+1+1+1+1+1+1+1", "256"] {
+            assert!(s.parse::<Grouping>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn group_starts() {
+        let grouping = "3+2+2".parse::<Grouping>().unwrap();
+        assert_eq!(
+            vec![0, 3, 5],
+            (0..7)
+                .filter(|&i| grouping.is_group_start(i))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn bits() {
+        let grouping = "3+3+2+1+1+1+2+3".parse::<Grouping>().unwrap();
+        assert_ne!(0, grouping.to_bits());
+        assert_eq!(Some(grouping), Grouping::from_bits(grouping.to_bits()));
+        assert_eq!(None, Grouping::from_bits(0));
+    }
+}
@@ -0,0 +1,398 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::{
+    BeatPosition, BeatUnit, Grouping, MusicalPosition, TimeSignatureBottom, TimeSignatureTop,
+    TICKS_PER_CROTCHET,
+};
+use crate::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+/// Time signature together with the grouping of its pulses and the note
+/// value counted as one beat
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Meter {
+    time_signature_top: TimeSignatureTop,
+    time_signature_bottom: TimeSignatureBottom,
+    grouping: Option<Grouping>,
+    beat_unit: Option<BeatUnit>,
+}
+
+impl Meter {
+    pub fn new(
+        time_signature_top: TimeSignatureTop,
+        time_signature_bottom: TimeSignatureBottom,
+    ) -> Self {
+        Self {
+            time_signature_top,
+            time_signature_bottom,
+            grouping: None,
+            beat_unit: None,
+        }
+    }
+
+    /// Groups the pulses of the bar: the grouping must account for every
+    /// pulse
+    pub fn with_grouping(self, grouping: Grouping) -> Result<Self, Error> {
+        if grouping.beat_count() == self.time_signature_top.as_number() {
+            Ok(Self {
+                grouping: Some(grouping),
+                ..self
+            })
+        } else {
+            Err(Error::InvalidGrouping)
+        }
+    }
+
+    /// Counts beats in the given unit instead of the conventional unit for
+    /// the meter
+    pub fn with_beat_unit(self, beat_unit: BeatUnit) -> Self {
+        Self {
+            beat_unit: Some(beat_unit),
+            ..self
+        }
+    }
+
+    pub fn time_signature_top(&self) -> TimeSignatureTop {
+        self.time_signature_top
+    }
+
+    pub fn time_signature_bottom(&self) -> TimeSignatureBottom {
+        self.time_signature_bottom
+    }
+
+    pub fn grouping(&self) -> Option<Grouping> {
+        self.grouping
+    }
+
+    pub fn beat_unit(&self) -> BeatUnit {
+        self.beat_unit.unwrap_or_else(|| {
+            BeatUnit::for_meter(
+                self.time_signature_top,
+                self.time_signature_bottom,
+                self.grouping,
+            )
+        })
+    }
+
+    /// Number of pulses (i.e. notes of the bottom number's value) per bar
+    pub fn pulse_count(&self) -> i32 {
+        self.time_signature_top.as_number()
+    }
+
+    /// Length of a pulse: exact for bottom numbers up to 16 as well as 32
+    /// and 64
+    pub fn pulse_length(&self) -> MusicalPosition {
+        MusicalPosition::from_ticks(
+            4 * TICKS_PER_CROTCHET / self.time_signature_bottom.as_number() as i64,
+        )
+    }
+
+    pub fn bar_length(&self) -> MusicalPosition {
+        MusicalPosition::from_ticks(self.pulse_count() as i64 * self.pulse_length().ticks())
+    }
+
+    pub fn beat_length_crotchets(&self) -> f64 {
+        self.beat_unit().length_crotchets(
+            self.time_signature_top,
+            self.time_signature_bottom,
+            self.grouping,
+        )
+    }
+
+    /// Number of beats of the beat unit per bar: the last beat is shorter
+    /// if the bar isn't a whole number of beats (e.g. 7/8 in crotchets)
+    pub fn beat_count(&self) -> i32 {
+        match self.fixed_beat_length() {
+            Some(beat_length) => {
+                let bar_length = self.bar_length().ticks();
+                ((bar_length + beat_length - 1) / beat_length) as i32
+            }
+            None => self
+                .grouping
+                .map_or(0, |grouping| grouping.group_count() as i32),
+        }
+    }
+
+    /// Start of the beat with the given index relative to the start of the
+    /// bar
+    pub fn beat_start(&self, index: i32) -> MusicalPosition {
+        match (self.fixed_beat_length(), self.grouping) {
+            (None, Some(grouping)) => {
+                let pulses = grouping
+                    .groups()
+                    .iter()
+                    .take(index.max(0) as usize)
+                    .map(|&group| group as i64)
+                    .sum::<i64>();
+                MusicalPosition::from_ticks(pulses * self.pulse_length().ticks())
+            }
+            (beat_length, _) => {
+                MusicalPosition::from_ticks(index as i64 * beat_length.unwrap_or_default())
+            }
+        }
+    }
+
+    /// Index of the pulse that the beat with the given index starts on if
+    /// it starts on a pulse
+    pub fn beat_pulse(&self, index: i32) -> Option<i32> {
+        let start = self.beat_start(index).ticks();
+        let pulse_length = self.pulse_length().ticks();
+        (start % pulse_length == 0).then_some((start / pulse_length) as i32)
+    }
+
+    /// Beat and ticks since the start of the beat at the given offset from
+    /// the start of the bar: offsets outside the bar give beat indices
+    /// outside the bar
+    pub fn beat_at(&self, offset: MusicalPosition) -> BeatPosition {
+        let offset = offset.ticks();
+        let beat_length = match (self.fixed_beat_length(), self.grouping) {
+            (None, Some(grouping)) if offset >= 0 => {
+                let pulse_length = self.pulse_length().ticks();
+                let mut start = 0;
+                for (index, &group) in grouping.groups().iter().enumerate() {
+                    let end = start + group as i64 * pulse_length;
+                    if offset < end {
+                        return BeatPosition {
+                            beat: index as i32,
+                            tick: offset - start,
+                        };
+                    }
+                    start = end;
+                }
+                return BeatPosition {
+                    beat: grouping.group_count() as i32,
+                    tick: offset - start,
+                };
+            }
+            (beat_length, _) => beat_length.unwrap_or_else(|| self.pulse_length().ticks()),
+        };
+        BeatPosition {
+            beat: offset.div_euclid(beat_length) as i32,
+            tick: offset.rem_euclid(beat_length),
+        }
+    }
+
+    /// Length of every beat in ticks unless beats follow the grouping
+    fn fixed_beat_length(&self) -> Option<i64> {
+        let pulse_length = self.pulse_length().ticks();
+        match self.beat_unit() {
+            BeatUnit::Pulse => Some(pulse_length),
+            BeatUnit::Grouped => match self.grouping {
+                Some(_) => None,
+                None => Some(self.time_signature_top.basis() as i64 * pulse_length),
+            },
+            beat_unit => Some(
+                MusicalPosition::from_crotchets(beat_unit.length_crotchets(
+                    self.time_signature_top,
+                    self.time_signature_bottom,
+                    self.grouping,
+                ))
+                .ticks(),
+            ),
+        }
+    }
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        Self::new(TimeSignatureTop::default(), TimeSignatureBottom::default())
+    }
+}
+
+/// Parses a time signature with an optional grouping (e.g. "7/8" or
+/// "7/8 (2+2+3)")
+impl FromStr for Meter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (time_signature, grouping) = match s.split_once('(') {
+            Some((time_signature, rest)) => match rest.trim_end().strip_suffix(')') {
+                Some(grouping) => (time_signature, Some(grouping.parse::<Grouping>()?)),
+                None => return Err(Error::InvalidGrouping),
+            },
+            None => (s, None),
+        };
+
+        let (top, bottom) = time_signature
+            .split_once('/')
+            .ok_or(Error::InvalidTimeSignatureBottom)?;
+        let top = top
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| Error::InvalidTimeSignatureTop)?;
+        let bottom = bottom
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| Error::InvalidTimeSignatureBottom)?;
+
+        let meter = Self::new(top.try_into()?, bottom.try_into()?);
+        match grouping {
+            Some(grouping) => meter.with_grouping(grouping),
+            None => Ok(meter),
+        }
+    }
+}
+
+impl Display for Meter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{}/{}",
+            self.time_signature_top, self.time_signature_bottom
+        )?;
+        if let Some(ref grouping) = self.grouping {
+            write!(f, " ({})", grouping)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{BeatUnit, MusicalPosition, TICKS_PER_CROTCHET};
+    use super::Meter;
+
+    fn beats(meter: &Meter, offsets: &[i64]) -> Vec<(i32, i64)> {
+        offsets
+            .iter()
+            .map(|&offset| {
+                let beat = meter.beat_at(MusicalPosition::from_ticks(offset));
+                (beat.beat, beat.tick)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse() {
+        let meter = "4/4".parse::<Meter>().unwrap();
+        assert_eq!(4, meter.time_signature_top().as_number());
+        assert_eq!(4, meter.time_signature_bottom().as_number());
+        assert_eq!(None, meter.grouping());
+        assert_eq!("4/4", meter.to_string());
+    }
+
+    #[test]
+    fn parse_grouping() {
+        let meter = "7/8 (2+2+3)".parse::<Meter>().unwrap();
+        assert_eq!(7, meter.time_signature_top().as_number());
+        assert_eq!(8, meter.time_signature_bottom().as_number());
+        assert_eq!("2+2+3", meter.grouping().unwrap().to_string());
+        assert_eq!("7/8 (2+2+3)", meter.to_string());
+        assert_eq!(meter, " 7 / 8 ( 2+2+3 ) ".parse().unwrap());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for s in [
+            "",
+            "7",
+            "x/8",
+            "7/x",
+            "0/4",
+            "4/0",
+            "4/17",
+            "7/8 (2+2)",
+            "7/8 (2+2+3",
+            "7/8 ()",
+        ] {
+            assert!(s.parse::<Meter>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn lengths() {
+        let meter = "7/8".parse::<Meter>().unwrap();
+        assert_eq!(TICKS_PER_CROTCHET / 2, meter.pulse_length().ticks());
+        assert_eq!(7 * TICKS_PER_CROTCHET / 2, meter.bar_length().ticks());
+
+        // A triplet minim
+        let meter = "4/3".parse::<Meter>().unwrap();
+        assert_eq!(4 * TICKS_PER_CROTCHET / 3, meter.pulse_length().ticks());
+        assert_eq!(16 * TICKS_PER_CROTCHET / 3, meter.bar_length().ticks());
+    }
+
+    #[test]
+    fn conventional_beats() {
+        let meter = "4/4".parse::<Meter>().unwrap();
+        assert_eq!(BeatUnit::Quarter, meter.beat_unit());
+        assert_eq!(4, meter.beat_count());
+        assert_eq!(1f64, meter.beat_length_crotchets());
+
+        let meter = "6/8".parse::<Meter>().unwrap();
+        assert_eq!(BeatUnit::DottedQuarter, meter.beat_unit());
+        assert_eq!(2, meter.beat_count());
+        assert_eq!(Some(3), meter.beat_pulse(1));
+
+        let meter = "5/12".parse::<Meter>().unwrap();
+        assert_eq!(BeatUnit::Pulse, meter.beat_unit());
+        assert_eq!(5, meter.beat_count());
+    }
+
+    #[test]
+    fn grouped_beats() {
+        let meter = "7/8 (2+2+3)".parse::<Meter>().unwrap();
+        let pulse = meter.pulse_length().ticks();
+        assert_eq!(BeatUnit::Grouped, meter.beat_unit());
+        assert_eq!(3, meter.beat_count());
+        assert_eq!(7f64 / 6f64, meter.beat_length_crotchets());
+        assert_eq!(
+            vec![Some(0), Some(2), Some(4)],
+            (0..3).map(|i| meter.beat_pulse(i)).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(0, 0), (0, 2 * pulse - 1), (1, 0), (2, pulse), (3, 0)],
+            beats(&meter, &[0, 2 * pulse - 1, 2 * pulse, 5 * pulse, 7 * pulse])
+        );
+    }
+
+    #[test]
+    fn partial_last_beat() {
+        let meter = "7/8"
+            .parse::<Meter>()
+            .unwrap()
+            .with_beat_unit(BeatUnit::Quarter);
+        assert_eq!(4, meter.beat_count());
+        assert_eq!(Some(6), meter.beat_pulse(3));
+        assert_eq!(
+            vec![(3, TICKS_PER_CROTCHET / 2 - 1), (4, 0)],
+            beats(
+                &meter,
+                &[meter.bar_length().ticks() - 1, 4 * TICKS_PER_CROTCHET]
+            )
+        );
+    }
+
+    #[test]
+    fn subdivided_beats() {
+        let meter = "4/4"
+            .parse::<Meter>()
+            .unwrap()
+            .with_beat_unit(BeatUnit::DottedEighth);
+        assert_eq!(6, meter.beat_count());
+        assert_eq!(
+            vec![Some(0), None, None, None, Some(3), None],
+            (0..6).map(|i| meter.beat_pulse(i)).collect::<Vec<_>>()
+        );
+    }
+}
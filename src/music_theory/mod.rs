@@ -19,6 +19,14 @@
 // OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
+mod beat_unit;
+mod grouping;
+mod meter;
+mod position;
 mod time_signature;
 
+pub use beat_unit::BeatUnit;
+pub use grouping::Grouping;
+pub use meter::Meter;
+pub use position::{BarBeatTick, BeatPosition, MusicalPosition, TICKS_PER_CROTCHET};
 pub use time_signature::{TimeSignatureBottom, TimeSignatureTop};
@@ -0,0 +1,245 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::Meter;
+use std::ops::{Add, Mul, Sub};
+
+/// Resolution of musical positions: divisible by every integer up to 16 so
+/// that the pulses of meters with any bottom number up to 16 (including
+/// irrational meters such as 4/3 or 5/12) and of 32nd and 64th notes are a
+/// whole number of ticks
+pub const TICKS_PER_CROTCHET: i64 = 720_720;
+
+/// Position in a song as a whole number of ticks from the start
+///
+/// Integer arithmetic keeps positions exact however many bars are added
+/// together
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct MusicalPosition(i64);
+
+/// Position within a bar as a beat index and the ticks since the start of
+/// that beat
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BeatPosition {
+    pub beat: i32,
+    pub tick: i64,
+}
+
+impl BeatPosition {
+    /// Time since the start of the beat in crotchets
+    pub fn crotchets(&self) -> f64 {
+        self.tick as f64 / TICKS_PER_CROTCHET as f64
+    }
+}
+
+/// Position in a song as a bar number, a beat index within that bar and the
+/// ticks since the start of that beat
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BarBeatTick {
+    pub bar: i32,
+    pub beat: i32,
+    pub tick: i64,
+}
+
+impl BarBeatTick {
+    /// Distance from the start of the beat
+    pub fn since_beat(&self) -> MusicalPosition {
+        MusicalPosition::from_ticks(self.tick)
+    }
+}
+
+impl MusicalPosition {
+    pub const fn from_ticks(ticks: i64) -> Self {
+        Self(ticks)
+    }
+
+    pub fn ticks(&self) -> i64 {
+        self.0
+    }
+
+    /// Position nearest to the given number of crotchets (e.g. from a host)
+    pub fn from_crotchets(crotchets: f64) -> Self {
+        Self((crotchets * TICKS_PER_CROTCHET as f64).round() as i64)
+    }
+
+    pub fn crotchets(&self) -> f64 {
+        self.0 as f64 / TICKS_PER_CROTCHET as f64
+    }
+
+    /// Distance covered in the given number of samples at a constant tempo
+    /// in crotchets per minute
+    ///
+    /// Computed from the total sample count so that rounding errors do not
+    /// accumulate
+    pub fn from_samples(samples: u64, tempo: f64, sample_rate: f32) -> Self {
+        Self::from_crotchets(samples as f64 * tempo / 60f64 / sample_rate as f64)
+    }
+
+    /// Number of samples taken to cover this distance at a constant tempo in
+    /// crotchets per minute
+    pub fn to_samples(&self, tempo: f64, sample_rate: f32) -> f64 {
+        self.crotchets() * 60f64 * sample_rate as f64 / tempo
+    }
+
+    /// Beat and tick of this position within the bar starting at the given
+    /// position counting beats in the meter's beat unit
+    pub fn beat_in_bar(&self, bar_start: Self, meter: &Meter) -> BeatPosition {
+        meter.beat_at(*self - bar_start)
+    }
+
+    /// Bar, beat and tick of this position given the number and start of
+    /// any bar: the position may be before that bar or bars after it
+    pub fn bar_beat_tick(&self, bar_number: i32, bar_start: Self, meter: &Meter) -> BarBeatTick {
+        let bar_length = meter.bar_length();
+        let bars = (*self - bar_start).ticks().div_euclid(bar_length.ticks());
+        let beat = self.beat_in_bar(bar_start + bar_length * bars, meter);
+        BarBeatTick {
+            bar: bar_number + bars as i32,
+            beat: beat.beat,
+            tick: beat.tick,
+        }
+    }
+}
+
+impl Add for MusicalPosition {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Mul<i64> for MusicalPosition {
+    type Output = Self;
+
+    fn mul(self, rhs: i64) -> Self::Output {
+        Self(self.0 * rhs)
+    }
+}
+
+impl Sub for MusicalPosition {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 - rhs.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BarBeatTick, MusicalPosition, TICKS_PER_CROTCHET};
+    use crate::music_theory::Meter;
+
+    #[test]
+    fn pulses_are_whole_ticks() {
+        for bottom in (1..=16).chain([32, 64]) {
+            assert_eq!(0, 4 * TICKS_PER_CROTCHET % bottom, "{}", bottom);
+        }
+    }
+
+    #[test]
+    fn crotchets() {
+        let pos = MusicalPosition::from_crotchets(2.5f64);
+        assert_eq!(5 * TICKS_PER_CROTCHET / 2, pos.ticks());
+        assert_eq!(2.5f64, pos.crotchets());
+        assert_eq!(
+            TICKS_PER_CROTCHET / 3,
+            MusicalPosition::from_crotchets(1f64 / 3f64).ticks()
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = MusicalPosition::from_ticks(3 * TICKS_PER_CROTCHET);
+        let b = MusicalPosition::from_ticks(TICKS_PER_CROTCHET / 2);
+        assert_eq!(7 * TICKS_PER_CROTCHET / 2, (a + b).ticks());
+        assert_eq!(5 * TICKS_PER_CROTCHET / 2, (a - b).ticks());
+        assert_eq!(-5 * TICKS_PER_CROTCHET / 2, (b - a).ticks());
+    }
+
+    #[test]
+    fn bars_add_up_exactly() {
+        let bar_length = "5/12".parse::<Meter>().unwrap().bar_length();
+        let pos = (0..12_000).fold(MusicalPosition::default(), |pos, _| pos + bar_length);
+        assert_eq!(20_000 * TICKS_PER_CROTCHET, pos.ticks());
+    }
+
+    #[test]
+    fn samples() {
+        // 120 crotchets per minute is one crotchet every 22050 samples
+        let pos = MusicalPosition::from_samples(22_050 * 3, 120f64, 44_100f32);
+        assert_eq!(3 * TICKS_PER_CROTCHET, pos.ticks());
+    }
+
+    #[test]
+    fn samples_round_trip() {
+        let pos = MusicalPosition::from_crotchets(3f64);
+        assert_eq!(66_150f64, pos.to_samples(120f64, 44_100f32));
+
+        let samples =
+            MusicalPosition::from_samples(1_234_567, 97f64, 48_000f32).to_samples(97f64, 48_000f32);
+        assert!((samples - 1_234_567f64).abs() < 0.1f64);
+    }
+
+    #[test]
+    fn beat_in_bar() {
+        let meter = "3/4".parse::<Meter>().unwrap();
+        let bar_start = MusicalPosition::from_ticks(6 * TICKS_PER_CROTCHET);
+        let beat = MusicalPosition::from_crotchets(7.5f64).beat_in_bar(bar_start, &meter);
+        assert_eq!(1, beat.beat);
+        assert_eq!(TICKS_PER_CROTCHET / 2, beat.tick);
+        assert_eq!(0.5f64, beat.crotchets());
+    }
+
+    #[test]
+    fn bar_beat_tick() {
+        let meter = "7/8 (2+2+3)".parse::<Meter>().unwrap();
+        let pulse = meter.pulse_length().ticks();
+        let bar_start = MusicalPosition::from_ticks(7 * pulse);
+        let bbt = |ticks| MusicalPosition::from_ticks(ticks).bar_beat_tick(4, bar_start, &meter);
+
+        assert_eq!(
+            BarBeatTick {
+                bar: 4,
+                beat: 0,
+                tick: 0
+            },
+            bbt(7 * pulse)
+        );
+        assert_eq!(
+            BarBeatTick {
+                bar: 6,
+                beat: 2,
+                tick: pulse + 1
+            },
+            bbt(26 * pulse + 1)
+        );
+        assert_eq!(
+            BarBeatTick {
+                bar: 3,
+                beat: 2,
+                tick: 2 * pulse
+            },
+            bbt(6 * pulse)
+        );
+        assert_eq!(pulse + 1, bbt(26 * pulse + 1).since_beat().ticks());
+    }
+}
@@ -19,15 +19,17 @@
 // OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
+use super::TICKS_PER_CROTCHET;
 use crate::error::Error;
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
 /// A time signature's top number (i.e. the count)
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "i32", into = "i32")]
 pub struct TimeSignatureTop(i32);
 
 impl TimeSignatureTop {
-    #[allow(dead_code)]
     pub fn as_number(&self) -> i32 {
         self.0
     }
@@ -72,6 +74,12 @@ impl TryFrom<i32> for TimeSignatureTop {
     }
 }
 
+impl From<TimeSignatureTop> for i32 {
+    fn from(value: TimeSignatureTop) -> Self {
+        value.0
+    }
+}
+
 impl Display for TimeSignatureTop {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{}", self.0)
@@ -79,7 +87,12 @@ impl Display for TimeSignatureTop {
 }
 
 /// A time signature's bottom number (i.e. the note value or quantum)
-#[derive(Clone, Copy)]
+///
+/// Irrational bottom numbers that some hosts report (e.g. 3 meaning a
+/// triplet minim) are accepted as long as a note of that value is a whole
+/// number of ticks
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "i32", into = "i32")]
 pub struct TimeSignatureBottom(i32);
 
 impl TimeSignatureBottom {
@@ -92,13 +105,20 @@ impl TryFrom<i32> for TimeSignatureBottom {
     type Error = Error;
 
     fn try_from(value: i32) -> Result<Self, Self::Error> {
-        match value {
-            2 | 4 | 8 | 16 => Ok(Self(value)),
-            _ => Err(Error::InvalidTimeSignatureBottom),
+        if value >= 1 && (4 * TICKS_PER_CROTCHET) % value as i64 == 0 {
+            Ok(Self(value))
+        } else {
+            Err(Error::InvalidTimeSignatureBottom)
         }
     }
 }
 
+impl From<TimeSignatureBottom> for i32 {
+    fn from(value: TimeSignatureBottom) -> Self {
+        value.0
+    }
+}
+
 impl Display for TimeSignatureBottom {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{}", self.0)
@@ -110,3 +130,42 @@ impl Default for TimeSignatureBottom {
         Self(4)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{TimeSignatureBottom, TimeSignatureTop};
+
+    #[test]
+    fn top_must_be_positive() {
+        assert!(TimeSignatureTop::try_from(0).is_err());
+        assert!(TimeSignatureTop::try_from(-4).is_err());
+        assert_eq!(7, TimeSignatureTop::try_from(7).unwrap().as_number());
+    }
+
+    #[test]
+    fn bottom_accepts_whole_tick_notes() {
+        for value in (1..=16).chain([32, 64, 2_882_880]) {
+            assert_eq!(
+                value,
+                TimeSignatureBottom::try_from(value).unwrap().as_number()
+            );
+        }
+    }
+
+    #[test]
+    fn bottom_rejects_fractional_tick_notes() {
+        for value in [0, -4, 17, 128, 2_882_881, 5_765_760, i32::MAX] {
+            assert!(TimeSignatureBottom::try_from(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn accents() {
+        let top = TimeSignatureTop::try_from(6).unwrap();
+        assert_eq!(3, top.basis());
+        assert_eq!(
+            vec![0, 3],
+            (0..6).filter(|&i| top.is_accented(i)).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+//! Entry points for the benchmarks, which are not part of the plugin's API
+use super::click::{Channel, Click, Waveform};
+use super::display::Playhead;
+use super::oscillator::{Oscillator, OscillatorQuality};
+use super::params::SuperClickParams;
+use super::plugin::SuperClick;
+use super::routing::{ChannelGains, RoutingParams};
+use crate::music_theory::{Meter, MusicalPosition};
+use nih_plug::prelude::{AuxiliaryBuffers, BoolParam, Buffer};
+
+/// Renders a continuous click tone into the channels a block at a time in
+/// the same way as the plugin
+pub struct ToneBench {
+    oscillator: Oscillator,
+    click: Click,
+    gains: ChannelGains,
+    scratch: Vec<f32>,
+}
+
+impl ToneBench {
+    pub fn new(sample_rate: f32, max_buffer_size: usize, channel_count: usize) -> Self {
+        let mut oscillator = Oscillator::default();
+        oscillator.initialize(sample_rate);
+        oscillator.update(0, 0);
+        Self {
+            oscillator,
+            click: Click {
+                channel: Channel::Both,
+                waveform: Waveform::Square,
+                frequency: 1000f32,
+                length: 1f64,
+                level: 1f32,
+            },
+            gains: RoutingParams::default().channel_gains(channel_count, Channel::Both),
+            scratch: vec![0f32; max_buffer_size],
+        }
+    }
+
+    pub fn render(&mut self, channels: &mut [&mut [f32]]) {
+        let samples = channels.first().map_or(0, |channel| channel.len());
+        let scratch = &mut self.scratch[..samples];
+        self.oscillator
+            .render(&self.click, OscillatorQuality::BandLimited, scratch);
+        self.gains.mix(channels, 0, scratch);
+    }
+}
+
+/// Buffer over the given channels
+pub fn new_buffer(channels: &mut [Vec<f32>], samples: usize) -> Buffer {
+    let mut buffer = Buffer::default();
+    // SAFETY: the slices outlive the buffer and all have the given length
+    unsafe {
+        buffer.set_slices(samples, |slices| {
+            slices.clear();
+            slices.extend(channels.iter_mut().map(|channel| channel.as_mut_slice()));
+        });
+    }
+    buffer
+}
+
+/// Plugin with default parameters apart from ducking, which is enabled so
+/// that the sidechain is followed, playing from its internal clock
+pub struct ProcessBench {
+    plugin: SuperClick,
+}
+
+impl ProcessBench {
+    pub fn new(sample_rate: f32, channel_count: usize, max_buffer_size: usize) -> Self {
+        let mut params = SuperClickParams::default();
+        params.ducking.enabled = BoolParam::new("Ducking", true);
+        let mut plugin = SuperClick::with_params(params);
+        plugin.prepare(sample_rate, channel_count, max_buffer_size);
+        Self { plugin }
+    }
+
+    pub fn process(&mut self, buffer: &mut Buffer, aux: &mut AuxiliaryBuffers) {
+        self.plugin.process_internal_clock(buffer, aux);
+    }
+}
+
+/// Finds the runs of samples within each beat of a playhead running at a
+/// steady tempo either by checking the beat at every sample or from the
+/// tempo as the plugin does
+pub struct BeatBench {
+    playhead: Playhead,
+    sample_rate: f32,
+}
+
+impl BeatBench {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            playhead: Playhead {
+                tempo: 120f64,
+                tempo_slope: 0f64,
+                bar_number: 0,
+                bar_start: MusicalPosition::default(),
+                pos: MusicalPosition::default(),
+                meter: Meter::default(),
+            },
+            sample_rate,
+        }
+    }
+
+    /// Number of runs in the next block finding the beat at every sample
+    pub fn per_sample(&mut self, samples: usize) -> usize {
+        let beat_at = |index: usize| {
+            let position = self
+                .playhead
+                .advance(index as i64, self.sample_rate)
+                .bar_beat_tick();
+            (position.bar, position.beat)
+        };
+        let mut runs = 0;
+        let mut last = None;
+        for index in 0..samples {
+            let beat = Some(beat_at(index));
+            if beat != last {
+                runs += 1;
+                last = beat;
+            }
+        }
+        self.next_block(samples);
+        runs
+    }
+
+    /// Number of runs in the next block finding the sample that each beat
+    /// ends on from the tempo
+    pub fn block(&mut self, samples: usize) -> usize {
+        let mut runs = 0;
+        let mut start = 0;
+        while start < samples {
+            let playhead = self.playhead.advance(start as i64, self.sample_rate);
+            let position = playhead.bar_beat_tick();
+            let next_beat = playhead.bar_start + playhead.meter.beat_start(position.beat + 1);
+            start = self
+                .playhead
+                .first_sample_at(next_beat, start + 1..samples, self.sample_rate);
+            runs += 1;
+        }
+        self.next_block(samples);
+        runs
+    }
+
+    fn next_block(&mut self, samples: usize) {
+        self.playhead = self.playhead.advance(samples as i64, self.sample_rate);
+    }
+}
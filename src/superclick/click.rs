@@ -19,37 +19,81 @@
 // OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
+use crate::music_theory::Meter;
+use serde::{Deserialize, Serialize};
+use std::f32::consts;
+
+/// Intensity tier of a click within a bar
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Tier {
+    Accent,
+    Subaccent,
+    Normal,
+}
+
+impl Tier {
+    /// Tier of the beat with the given index: beats that start on an
+    /// accented pulse are subaccents
+    pub fn for_beat(meter: &Meter, index: i32) -> Self {
+        let is_accented = meter
+            .beat_pulse(index)
+            .map_or(false, |pulse| match meter.grouping() {
+                Some(grouping) => grouping.is_group_start(pulse),
+                None => meter.time_signature_top().is_accented(pulse),
+            });
+
+        if index == 0 {
+            Self::Accent
+        } else if is_accented {
+            Self::Subaccent
+        } else {
+            Self::Normal
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Channel {
     Left,
     Right,
     Both,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Waveform {
+    Sine,
+    Square,
+    Saw,
+    Triangle,
+}
+
+impl Waveform {
+    /// Value of the waveform at the given phase in the range [0, 1)
+    pub fn value_at(&self, phase: f32) -> f32 {
+        match self {
+            Self::Sine => (phase * consts::TAU).sin(),
+            Self::Square => {
+                if phase < 0.5f32 {
+                    1f32
+                } else {
+                    -1f32
+                }
+            }
+            Self::Saw => 2f32 * phase - 1f32,
+            Self::Triangle => 4f32 * (phase - 0.5f32).abs() - 1f32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub struct Click {
     pub channel: Channel,
+    pub waveform: Waveform,
     pub frequency: f32,
     pub length: f64,
-}
 
-impl Click {
-    /// Body Beat Pulse Solo accent (high-intensity) click
-    pub const ACCENT: Click = Click {
-        channel: Channel::Right,
-        frequency: 400f32,
-        length: 0.125f64,
-    };
-
-    /// Body Beat Pulse Solo subaccent (medium-intensity) click
-    pub const SUBACCENT: Click = Click {
-        channel: Channel::Left,
-        frequency: 800f32,
-        length: 0.125f64,
-    };
-
-    /// Body Beat Pulse Solo normal (low-intensity) click
-    pub const NORMAL: Click = Click {
-        channel: Channel::Both,
-        frequency: 1_600f32,
-        length: 0.125f64,
-    };
+    /// Linear gain
+    pub level: f32,
 }
@@ -0,0 +1,377 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::display::Playhead;
+use super::setlist::Song;
+use super::trainer::{new_tempo_param, Trainer, TrainerParams};
+use crate::music_theory::{BeatUnit, Meter, MusicalPosition};
+use crate::result::Result;
+use nih_plug::prelude::*;
+
+/// Source of tempo, meter and song position
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum ClockSource {
+    /// Follow the host's transport
+    Host,
+
+    /// Run from the plugin's own clock
+    Internal,
+}
+
+/// Time signature bottom numbers available to the internal clock
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum Denominator {
+    #[name = "1"]
+    One,
+    #[name = "2"]
+    Two,
+    #[name = "4"]
+    Four,
+    #[name = "8"]
+    Eight,
+    #[name = "16"]
+    Sixteen,
+    #[name = "32"]
+    ThirtyTwo,
+}
+
+impl Denominator {
+    pub fn as_number(&self) -> i32 {
+        match self {
+            Self::One => 1,
+            Self::Two => 2,
+            Self::Four => 4,
+            Self::Eight => 8,
+            Self::Sixteen => 16,
+            Self::ThirtyTwo => 32,
+        }
+    }
+}
+
+/// Note value that tempos are expressed in
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum TempoUnit {
+    /// Conventional beat unit for the meter
+    Auto,
+    Half,
+    #[name = "Dotted half"]
+    DottedHalf,
+    Quarter,
+    #[name = "Dotted quarter"]
+    DottedQuarter,
+    Eighth,
+    #[name = "Dotted eighth"]
+    DottedEighth,
+    Sixteenth,
+
+    /// One beat per note of the time signature's bottom number
+    Pulse,
+
+    /// One beat per group of the accent pattern
+    Grouped,
+}
+
+impl TempoUnit {
+    /// Meter counting beats in this unit
+    pub fn apply(&self, meter: Meter) -> Meter {
+        let beat_unit = match self {
+            Self::Auto => return meter,
+            Self::Half => BeatUnit::Half,
+            Self::DottedHalf => BeatUnit::DottedHalf,
+            Self::Quarter => BeatUnit::Quarter,
+            Self::DottedQuarter => BeatUnit::DottedQuarter,
+            Self::Eighth => BeatUnit::Eighth,
+            Self::DottedEighth => BeatUnit::DottedEighth,
+            Self::Sixteenth => BeatUnit::Sixteenth,
+            Self::Pulse => BeatUnit::Pulse,
+            Self::Grouped => BeatUnit::Grouped,
+        };
+        meter.with_beat_unit(beat_unit)
+    }
+}
+
+/// Notes that the click plays on
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum ClickOn {
+    /// Every note of the time signature's bottom number
+    Pulse,
+
+    /// Every beat of the beat unit
+    Beat,
+}
+
+impl ClickOn {
+    /// Meter whose beats the click plays on given the meter counting beats
+    /// in the beat unit
+    pub fn apply(&self, meter: Meter) -> Meter {
+        match self {
+            Self::Pulse => meter.with_beat_unit(BeatUnit::Pulse),
+            Self::Beat => meter,
+        }
+    }
+}
+
+#[derive(Params)]
+pub struct ClockParams {
+    #[id = "source"]
+    pub source: EnumParam<ClockSource>,
+
+    #[id = "run"]
+    pub run: BoolParam,
+
+    #[id = "tempo"]
+    pub tempo: FloatParam,
+
+    #[id = "beat-unit"]
+    pub beat_unit: EnumParam<TempoUnit>,
+
+    #[id = "click-on"]
+    pub click_on: EnumParam<ClickOn>,
+
+    #[id = "time-signature-top"]
+    pub time_signature_top: IntParam,
+
+    #[id = "time-signature-bottom"]
+    pub time_signature_bottom: EnumParam<Denominator>,
+
+    #[nested(id_prefix = "trainer", group = "Speed trainer")]
+    pub trainer: TrainerParams,
+}
+
+impl ClockParams {
+    /// Meter set by the parameters counting beats in the selected beat unit
+    pub fn meter(&self) -> Result<Meter> {
+        Ok(self.beat_unit.value().apply(Meter::new(
+            self.time_signature_top.value().try_into()?,
+            self.time_signature_bottom.value().as_number().try_into()?,
+        )))
+    }
+
+    /// Length of a beat of the meter set by the parameters
+    pub fn beat_length_crotchets(&self) -> f64 {
+        self.meter()
+            .map_or(1f64, |meter| meter.beat_length_crotchets())
+    }
+
+    /// Source of the playhead: the standalone application has no host
+    /// transport so always uses the internal clock
+    pub fn clock_source(&self, plugin_api: PluginApi) -> ClockSource {
+        if plugin_api == PluginApi::Standalone {
+            ClockSource::Internal
+        } else {
+            self.source.value()
+        }
+    }
+}
+
+impl Default for ClockParams {
+    fn default() -> Self {
+        Self {
+            source: EnumParam::new("Clock", ClockSource::Host),
+            run: BoolParam::new("Run", false),
+            tempo: new_tempo_param("Tempo", 120f32),
+            beat_unit: EnumParam::new("Beat unit", TempoUnit::Auto),
+            click_on: EnumParam::new("Click on", ClickOn::Pulse),
+            time_signature_top: IntParam::new(
+                "Time signature top",
+                4,
+                IntRange::Linear { min: 1, max: 32 },
+            ),
+            time_signature_bottom: EnumParam::new("Time signature bottom", Denominator::Four),
+            trainer: TrainerParams::default(),
+        }
+    }
+}
+
+/// Tempo and meter the internal clock runs at
+#[derive(Clone, Copy)]
+pub struct ClockSettings {
+    /// Tempo in crotchets per minute
+    pub tempo: f64,
+
+    /// Meter with the beat unit that the tempo and trainer are expressed in
+    pub meter: Meter,
+
+    pub count_in_bars: i32,
+
+    /// Tempo ramp overriding the tempo
+    pub trainer: Option<Trainer>,
+}
+
+impl ClockSettings {
+    /// Settings from the current song if there is one, otherwise from the
+    /// clock parameters
+    pub fn new(params: &ClockParams, song: Option<Song>) -> Result<Self> {
+        let (tempo, meter, count_in_bars) = match song {
+            Some(song) => {
+                let meter = params.beat_unit.value().apply(song.meter()?);
+                (song.crotchets_per_minute(&meter), meter, song.count_in_bars)
+            }
+            None => (params.tempo.value() as f64, params.meter()?, 0),
+        };
+
+        Ok(Self {
+            tempo,
+            meter,
+            count_in_bars,
+            trainer: params.trainer.trainer(),
+        })
+    }
+}
+
+/// Clock that generates a playhead independently of the host's transport
+#[derive(Default)]
+pub struct InternalClock {
+    is_running: bool,
+    bar_number: i32,
+    bar_start: MusicalPosition,
+
+    /// Position, tempo and samples elapsed since the tempo last changed:
+    /// positions are computed from the sample count rather than summed so
+    /// that they do not drift
+    segment_start: MusicalPosition,
+    segment_tempo: f64,
+    segment_samples: u64,
+}
+
+impl InternalClock {
+    /// Playhead at the start of a block of the given length, advancing the
+    /// clock to the end of the block
+    pub fn tick(
+        &mut self,
+        run: bool,
+        settings: &ClockSettings,
+        samples: usize,
+        sample_rate: f32,
+    ) -> Option<Playhead> {
+        if !run {
+            self.reset();
+            return None;
+        }
+
+        let bar_length = settings.meter.bar_length();
+
+        // Count-in bars have negative bar numbers and end at position zero
+        if !self.is_running {
+            self.is_running = true;
+            self.bar_number = -settings.count_in_bars;
+            self.bar_start = bar_length * self.bar_number as i64;
+            self.segment_start = self.bar_start;
+            self.segment_samples = 0;
+        }
+
+        let pos = self.segment_start
+            + MusicalPosition::from_samples(self.segment_samples, self.segment_tempo, sample_rate);
+
+        while pos >= self.bar_start + bar_length {
+            self.bar_start = self.bar_start + bar_length;
+            self.bar_number += 1;
+        }
+
+        let tempo = settings.trainer.map_or(settings.tempo, |trainer| {
+            trainer.progress(self.bar_number).tempo
+        });
+
+        if tempo != self.segment_tempo {
+            self.segment_start = pos;
+            self.segment_tempo = tempo;
+            self.segment_samples = 0;
+        }
+
+        self.segment_samples += samples as u64;
+
+        Some(Playhead {
+            tempo,
+            tempo_slope: 0f64,
+            bar_number: self.bar_number,
+            bar_start: self.bar_start,
+            pos,
+            meter: settings.meter,
+        })
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClickOn, ClockSettings, InternalClock};
+    use crate::music_theory::{Meter, MusicalPosition, TICKS_PER_CROTCHET};
+
+    const SAMPLE_RATE: f32 = 192_000f32;
+    const BLOCK_SAMPLES: u64 = 4_096;
+
+    // There is a beat every 96000 samples at 120 crotchets per minute
+    const BEAT_SAMPLES: u64 = 96_000;
+
+    #[test]
+    fn beats_do_not_drift_over_hours() {
+        let settings = ClockSettings {
+            tempo: 120f64,
+            meter: "4/4".parse::<Meter>().unwrap(),
+            count_in_bars: 0,
+            trainer: None,
+        };
+        let mut clock = InternalClock::default();
+        let total_samples = 3 * 60 * 60 * SAMPLE_RATE as u64;
+
+        let mut start = 0;
+        while start < total_samples {
+            let playhead = clock
+                .tick(true, &settings, BLOCK_SAMPLES as usize, SAMPLE_RATE)
+                .unwrap();
+
+            let onset = start.next_multiple_of(BEAT_SAMPLES);
+            if onset < start + BLOCK_SAMPLES {
+                let offset = (onset - start) as i64;
+                let index = (onset / BEAT_SAMPLES) as i64;
+                let beat = playhead.advance(offset, SAMPLE_RATE);
+                assert_eq!(
+                    MusicalPosition::from_ticks(index * TICKS_PER_CROTCHET),
+                    beat.pos
+                );
+                assert_eq!((index / 4) as i32, beat.bar_number);
+                assert!(playhead.advance(offset - 1, SAMPLE_RATE).pos < beat.pos);
+            }
+
+            start += BLOCK_SAMPLES;
+        }
+
+        let playhead = clock
+            .tick(true, &settings, BLOCK_SAMPLES as usize, SAMPLE_RATE)
+            .unwrap();
+        assert_eq!(
+            MusicalPosition::from_ticks(3 * 60 * 120 * TICKS_PER_CROTCHET),
+            playhead.pos
+        );
+        assert_eq!(3 * 60 * 30, playhead.bar_number);
+    }
+
+    #[test]
+    fn click_on_pulses_by_default() {
+        let meter = "6/8".parse::<Meter>().unwrap();
+        assert_eq!(2, meter.beat_count());
+        assert_eq!(6, ClickOn::Pulse.apply(meter).beat_count());
+        assert_eq!(2, ClickOn::Beat.apply(meter).beat_count());
+    }
+}
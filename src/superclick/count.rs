@@ -0,0 +1,176 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::sample::Sample;
+use nih_plug::nih_log;
+use nih_plug::prelude::Enum;
+use std::path::Path;
+
+/// Number of spoken numbers in a recorded count set (i.e. `1.wav` to
+/// `16.wav`)
+const MAX_COUNT: usize = 16;
+
+/// Count set bundled with the plugin used for numbers without a recording
+/// of the user's own
+const BUNDLED_COUNTS: [&[u8]; MAX_COUNT] = [
+    include_bytes!("../../counts/1.wav"),
+    include_bytes!("../../counts/2.wav"),
+    include_bytes!("../../counts/3.wav"),
+    include_bytes!("../../counts/4.wav"),
+    include_bytes!("../../counts/5.wav"),
+    include_bytes!("../../counts/6.wav"),
+    include_bytes!("../../counts/7.wav"),
+    include_bytes!("../../counts/8.wav"),
+    include_bytes!("../../counts/9.wav"),
+    include_bytes!("../../counts/10.wav"),
+    include_bytes!("../../counts/11.wav"),
+    include_bytes!("../../counts/12.wav"),
+    include_bytes!("../../counts/13.wav"),
+    include_bytes!("../../counts/14.wav"),
+    include_bytes!("../../counts/15.wav"),
+    include_bytes!("../../counts/16.wav"),
+];
+
+/// Sound used for each beat
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum ClickVoice {
+    /// Tones from the device profile
+    Tone,
+
+    /// Spoken beat numbers from the bundled or a recorded count set
+    /// falling back to tones for beats after the sixteenth
+    Count,
+}
+
+/// Spoken numbers prepared for playback on the audio thread
+#[derive(Default)]
+pub struct CountBank {
+    samples: Vec<Option<Sample>>,
+}
+
+impl CountBank {
+    /// Loads a recorded count set from a directory containing one WAV file
+    /// per number using the bundled count set for numbers without one
+    pub fn load(dir: Option<&Path>) -> Self {
+        let samples = BUNDLED_COUNTS
+            .iter()
+            .enumerate()
+            .map(|(i, bytes)| {
+                dir.and_then(|dir| Self::read_recording(dir, i + 1))
+                    .or_else(|| Sample::parse(bytes).ok())
+            })
+            .collect();
+        Self { samples }
+    }
+
+    fn read_recording(dir: &Path, number: usize) -> Option<Sample> {
+        match Sample::read(&dir.join(format!("{}.wav", number))) {
+            Ok(sample) => Some(sample),
+            Err(_) => {
+                nih_log!("No recording for count {}: using bundled count", number);
+                None
+            }
+        }
+    }
+
+    /// Length in seconds of the recording for the beat with the given
+    /// index if there is one
+    pub fn duration(&self, index: i32) -> Option<f64> {
+        self.sample(index).map(Sample::duration)
+    }
+
+    fn sample(&self, index: i32) -> Option<&Sample> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.samples.get(index)?.as_ref())
+    }
+
+    fn value_at(&self, index: i32, seconds: f64) -> Option<f32> {
+        self.sample(index)
+            .filter(|sample| seconds < sample.duration())
+            .map(|sample| sample.value_at(seconds))
+    }
+}
+
+/// Plays the number for each beat from a count bank in time with the
+/// playhead
+#[derive(Default)]
+pub struct CountPlayer {
+    beat: Option<(i32, i32)>,
+
+    /// Number and samples played so far
+    count: Option<(i32, u64)>,
+}
+
+impl CountPlayer {
+    /// Starts the count for the beat on entering a new beat
+    pub fn update(&mut self, bar_number: i32, index: i32) {
+        if self.beat != Some((bar_number, index)) {
+            self.beat = Some((bar_number, index));
+            self.count = Some((index, 0));
+        }
+    }
+
+    /// Renders the current count into the output, which is silent after
+    /// the count ends
+    pub fn render(&mut self, bank: &CountBank, output: &mut [f32], sample_rate: f32) {
+        let (index, mut samples) = match self.count {
+            Some(count) => count,
+            None => {
+                output.fill(0f32);
+                return;
+            }
+        };
+
+        for (i, value) in output.iter_mut().enumerate() {
+            match bank.value_at(index, samples as f64 / sample_rate as f64) {
+                Some(v) => *value = v,
+                None => {
+                    output[i..].fill(0f32);
+                    self.count = None;
+                    return;
+                }
+            }
+            samples += 1;
+        }
+
+        self.count = Some((index, samples));
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CountBank, MAX_COUNT};
+
+    #[test]
+    fn bundled_counts_are_valid() {
+        let bank = CountBank::load(None);
+        for index in 0..MAX_COUNT as i32 {
+            let duration = bank.duration(index).expect("must have bundled count");
+            assert!(duration > 0f64 && duration < 1f64);
+        }
+        assert_eq!(None, bank.duration(MAX_COUNT as i32));
+    }
+}
@@ -0,0 +1,218 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::sample::Sample;
+use crate::error::Error;
+use crate::result::Result;
+use nih_plug::nih_log;
+use serde::{Deserialize, Serialize};
+use std::f64::consts;
+use std::path::PathBuf;
+
+const CHIME_TONE_SECONDS: f64 = 0.15;
+const CHIME_FREQUENCIES: [f64; 2] = [660f64, 880f64];
+
+/// Section label announced during the bar before the given bar
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Cue {
+    pub bar: i32,
+    pub label: String,
+}
+
+/// Cues for a song together with the directory containing recordings of
+/// their labels
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CueTrack {
+    /// Directory containing one WAV file per label (e.g. `verse.wav` or
+    /// `guitar-solo.wav`)
+    pub sample_dir: Option<PathBuf>,
+
+    /// Cues in bar order
+    pub cues: Vec<Cue>,
+}
+
+impl CueTrack {
+    /// Adds a cue replacing any existing cue at the same bar
+    pub fn add(&mut self, bar: i32, label: &str) -> Result<()> {
+        if bar < 1 {
+            return Err(Error::InvalidCueBar);
+        }
+
+        let label = label.trim();
+        if label.is_empty() {
+            return Err(Error::InvalidCueLabel);
+        }
+
+        self.cues.retain(|cue| cue.bar != bar);
+        self.cues.push(Cue {
+            bar,
+            label: String::from(label),
+        });
+        self.cues.sort_by_key(|cue| cue.bar);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.cues.len() {
+            self.cues.remove(index);
+        }
+    }
+
+    fn sample_file_name(label: &str) -> String {
+        format!(
+            "{}.wav",
+            label
+                .trim()
+                .to_lowercase()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join("-")
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Voice {
+    Sample(usize),
+    Chime,
+}
+
+/// Cue track prepared for playback on the audio thread
+#[derive(Default)]
+pub struct CueBank {
+    cues: Vec<(i32, Voice)>,
+    samples: Vec<Sample>,
+}
+
+impl CueBank {
+    /// Loads recordings for each of the track's labels: labels without a
+    /// recording are announced with a chime instead
+    pub fn load(track: &CueTrack) -> Self {
+        let mut bank = Self::default();
+        let mut labels = Vec::<String>::new();
+        for cue in &track.cues {
+            let file_name = CueTrack::sample_file_name(&cue.label);
+            let voice = match labels.iter().position(|l| *l == file_name) {
+                Some(index) => Voice::Sample(index),
+                None => match track
+                    .sample_dir
+                    .as_ref()
+                    .map(|dir| Sample::read(&dir.join(&file_name)))
+                {
+                    Some(Ok(sample)) => {
+                        labels.push(file_name);
+                        bank.samples.push(sample);
+                        Voice::Sample(bank.samples.len() - 1)
+                    }
+                    _ => {
+                        nih_log!("No recording for cue \"{}\": using chime", cue.label);
+                        Voice::Chime
+                    }
+                },
+            };
+            bank.cues.push((cue.bar, voice));
+        }
+        bank
+    }
+
+    fn voice_for_bar(&self, bar_number: i32) -> Option<Voice> {
+        self.cues
+            .iter()
+            .find(|(bar, _)| *bar == bar_number)
+            .map(|(_, voice)| *voice)
+    }
+
+    fn value_at(&self, voice: Voice, seconds: f64) -> Option<f32> {
+        match voice {
+            // The bank may have been reloaded since the announcement started
+            Voice::Sample(index) => self
+                .samples
+                .get(index)
+                .filter(|sample| seconds < sample.duration())
+                .map(|sample| sample.value_at(seconds)),
+            Voice::Chime => chime_value_at(seconds),
+        }
+    }
+}
+
+/// Plays announcements from a cue bank in time with the playhead
+#[derive(Default)]
+pub struct CuePlayer {
+    bar_number: Option<i32>,
+    /// Voice and samples played so far
+    announcement: Option<(Voice, u64)>,
+}
+
+impl CuePlayer {
+    /// Starts the announcement for the next bar's cue on entering a new bar
+    pub fn update(&mut self, bank: &CueBank, bar_number: i32) {
+        if self.bar_number != Some(bar_number) {
+            self.bar_number = Some(bar_number);
+            if let Some(voice) = bank.voice_for_bar(bar_number + 1) {
+                self.announcement = Some((voice, 0));
+            }
+        }
+    }
+
+    /// Whether an announcement is playing
+    pub fn is_announcing(&self) -> bool {
+        self.announcement.is_some()
+    }
+
+    /// Renders the current announcement into the output, which is silent
+    /// after the announcement ends
+    pub fn render(&mut self, bank: &CueBank, output: &mut [f32], sample_rate: f32) {
+        let (voice, mut samples) = match self.announcement {
+            Some(announcement) => announcement,
+            None => {
+                output.fill(0f32);
+                return;
+            }
+        };
+
+        for (i, value) in output.iter_mut().enumerate() {
+            match bank.value_at(voice, samples as f64 / sample_rate as f64) {
+                Some(v) => *value = v,
+                None => {
+                    output[i..].fill(0f32);
+                    self.announcement = None;
+                    return;
+                }
+            }
+            samples += 1;
+        }
+
+        self.announcement = Some((voice, samples));
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Two-tone chime with a decaying envelope
+fn chime_value_at(seconds: f64) -> Option<f32> {
+    let index = (seconds / CHIME_TONE_SECONDS).trunc() as usize;
+    let frequency = CHIME_FREQUENCIES.get(index)?;
+    let t = seconds - index as f64 * CHIME_TONE_SECONDS;
+    let envelope = 1f64 - t / CHIME_TONE_SECONDS;
+    Some(((t * frequency * consts::TAU).sin() * envelope * 0.5f64) as f32)
+}
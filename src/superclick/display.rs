@@ -20,17 +20,93 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
 use crate::error::Error;
-use crate::music_theory::{TimeSignatureBottom, TimeSignatureTop};
+use crate::music_theory::{
+    BarBeatTick, Grouping, Meter, MusicalPosition, TimeSignatureBottom, TimeSignatureTop,
+};
 use atomic_float::AtomicF64;
-use std::sync::atomic::{AtomicBool, AtomicI32, AtomicIsize, Ordering};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicI64, AtomicIsize, AtomicU64, Ordering};
 
+#[derive(Clone, Copy)]
 pub struct Playhead {
+    /// Tempo in crotchets per minute
     pub tempo: f64,
+
+    /// Change in tempo per sample across the block
+    pub tempo_slope: f64,
+
     pub bar_number: i32,
-    pub bar_start_pos_crotchets: f64,
-    pub pos_crotchets: f64,
-    pub time_signature_top: TimeSignatureTop,
-    pub time_signature_bottom: TimeSignatureBottom,
+    pub bar_start: MusicalPosition,
+    pub pos: MusicalPosition,
+    pub meter: Meter,
+}
+
+impl Playhead {
+    /// Playhead the given number of samples into the block integrating the
+    /// tempo over the samples
+    ///
+    /// Negative sample counts give the playhead before the block
+    pub fn advance(&self, samples: i64, sample_rate: f32) -> Self {
+        let samples = samples as f64;
+        let crotchets =
+            (self.tempo + self.tempo_slope * samples / 2f64) * samples / 60f64 / sample_rate as f64;
+        let pos = self.pos + MusicalPosition::from_crotchets(crotchets);
+        let bar_number = pos
+            .bar_beat_tick(self.bar_number, self.bar_start, &self.meter)
+            .bar;
+        Self {
+            tempo: self.tempo + self.tempo_slope * samples,
+            bar_number,
+            bar_start: self.bar_start
+                + self.meter.bar_length() * (bar_number - self.bar_number) as i64,
+            pos,
+            ..*self
+        }
+    }
+
+    pub fn bar_beat_tick(&self) -> BarBeatTick {
+        self.pos
+            .bar_beat_tick(self.bar_number, self.bar_start, &self.meter)
+    }
+
+    /// First of the given samples into the block at which the playhead has
+    /// reached the given position or the end of the range if it doesn't
+    ///
+    /// The sample is estimated from the tempo and then checked against
+    /// `advance` so that it agrees with the playhead at each sample
+    pub fn first_sample_at(
+        &self,
+        target: MusicalPosition,
+        samples: Range<usize>,
+        sample_rate: f32,
+    ) -> usize {
+        let is_reached = |index: usize| self.advance(index as i64, sample_rate).pos >= target;
+        let mut index = self
+            .samples_to(target, sample_rate)
+            .map_or(samples.end, |estimate| estimate.max(0f64).ceil() as usize)
+            .clamp(samples.start, samples.end);
+        while index > samples.start && is_reached(index - 1) {
+            index -= 1;
+        }
+        while index < samples.end && !is_reached(index) {
+            index += 1;
+        }
+        index
+    }
+
+    /// Samples taken to reach the given position integrating the tempo over
+    /// the samples if the playhead ever reaches it
+    fn samples_to(&self, target: MusicalPosition, sample_rate: f32) -> Option<f64> {
+        let samples = (target - self.pos).to_samples(self.tempo, sample_rate);
+        if self.tempo_slope == 0f64 {
+            return Some(samples);
+        }
+
+        // Solves (tempo + slope * s / 2) * s = tempo * samples
+        let distance = self.tempo * samples;
+        let discriminant = self.tempo * self.tempo + 2f64 * self.tempo_slope * distance;
+        (discriminant >= 0f64).then(|| 2f64 * distance / (self.tempo + discriminant.sqrt()))
+    }
 }
 
 #[derive(Default)]
@@ -39,10 +115,11 @@ pub struct Display {
     is_playing: AtomicBool,
     tempo: AtomicF64,
     bar_number: AtomicI32,
-    bar_start_pos_crotchets: AtomicF64,
-    pos_crotchets: AtomicF64,
+    bar_start_ticks: AtomicI64,
+    pos_ticks: AtomicI64,
     time_signature_top: AtomicI32,
     time_signature_bottom: AtomicI32,
+    grouping: AtomicU64,
 }
 
 impl Display {
@@ -62,31 +139,45 @@ impl Display {
         self.bar_number.load(Ordering::Relaxed)
     }
 
-    pub fn bar_start_pos_crotchets(&self) -> f64 {
-        self.bar_start_pos_crotchets.load(Ordering::Relaxed)
+    pub fn bar_start(&self) -> MusicalPosition {
+        MusicalPosition::from_ticks(self.bar_start_ticks.load(Ordering::Relaxed))
     }
 
-    pub fn pos_crotchets(&self) -> f64 {
-        self.pos_crotchets.load(Ordering::Relaxed)
+    pub fn pos(&self) -> MusicalPosition {
+        MusicalPosition::from_ticks(self.pos_ticks.load(Ordering::Relaxed))
     }
 
-    pub fn time_signature_top(&self) -> Option<TimeSignatureTop> {
-        self.time_signature_top
-            .load(Ordering::Relaxed)
-            .try_into()
-            .ok()
+    /// Bar, beat and tick of the position counting beats in the given meter's
+    /// beat unit
+    pub fn bar_beat_tick(&self, meter: &Meter) -> BarBeatTick {
+        self.pos()
+            .bar_beat_tick(self.bar_number(), self.bar_start(), meter)
     }
 
-    pub fn time_signature_bottom(&self) -> Option<TimeSignatureBottom> {
-        self.time_signature_bottom
+    /// Meter without its beat unit which is not stored
+    pub fn meter(&self) -> Option<Meter> {
+        let time_signature_top: TimeSignatureTop = self
+            .time_signature_top
+            .load(Ordering::Relaxed)
+            .try_into()
+            .ok()?;
+        let time_signature_bottom: TimeSignatureBottom = self
+            .time_signature_bottom
             .load(Ordering::Relaxed)
             .try_into()
-            .ok()
+            .ok()?;
+        let meter = Meter::new(time_signature_top, time_signature_bottom);
+        Some(
+            match Grouping::from_bits(self.grouping.load(Ordering::Relaxed)) {
+                Some(grouping) => meter.with_grouping(grouping).unwrap_or(meter),
+                None => meter,
+            },
+        )
     }
 
     pub fn update(&self, error: Option<Error>, playhead: &Option<Playhead>) {
         if let Some(error) = error {
-            self.error_code.store(error as isize, Ordering::Relaxed);
+            self.error_code.store(error.code(), Ordering::Relaxed);
         } else {
             self.error_code.store(isize::default(), Ordering::Relaxed);
         }
@@ -96,14 +187,23 @@ impl Display {
             self.tempo.store(playhead.tempo, Ordering::Relaxed);
             self.bar_number
                 .store(playhead.bar_number, Ordering::Relaxed);
-            self.bar_start_pos_crotchets
-                .store(playhead.bar_start_pos_crotchets, Ordering::Relaxed);
-            self.pos_crotchets
-                .store(playhead.pos_crotchets, Ordering::Relaxed);
-            self.time_signature_top
-                .store(playhead.time_signature_top.as_number(), Ordering::Relaxed);
+            self.bar_start_ticks
+                .store(playhead.bar_start.ticks(), Ordering::Relaxed);
+            self.pos_ticks
+                .store(playhead.pos.ticks(), Ordering::Relaxed);
+            self.time_signature_top.store(
+                playhead.meter.time_signature_top().as_number(),
+                Ordering::Relaxed,
+            );
             self.time_signature_bottom.store(
-                playhead.time_signature_bottom.as_number(),
+                playhead.meter.time_signature_bottom().as_number(),
+                Ordering::Relaxed,
+            );
+            self.grouping.store(
+                playhead
+                    .meter
+                    .grouping()
+                    .map_or(0, |grouping| grouping.to_bits()),
                 Ordering::Relaxed,
             );
         } else {
@@ -111,3 +211,74 @@ impl Display {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Playhead;
+    use crate::music_theory::{Meter, MusicalPosition, TICKS_PER_CROTCHET};
+
+    const SAMPLE_RATE: f32 = 44_100f32;
+    const SAMPLES: usize = 4_096;
+
+    fn playhead(tempo: f64, tempo_slope: f64) -> Playhead {
+        Playhead {
+            tempo,
+            tempo_slope,
+            bar_number: 3,
+            bar_start: MusicalPosition::from_ticks(12 * TICKS_PER_CROTCHET),
+            pos: MusicalPosition::from_crotchets(14.9f64),
+            meter: "4/4".parse::<Meter>().unwrap(),
+        }
+    }
+
+    /// First sample at which the playhead reaches the position found by
+    /// checking every sample
+    fn first_sample_at(playhead: &Playhead, target: MusicalPosition) -> usize {
+        (0..SAMPLES)
+            .find(|&index| playhead.advance(index as i64, SAMPLE_RATE).pos >= target)
+            .unwrap_or(SAMPLES)
+    }
+
+    #[test]
+    fn first_sample_at_agrees_with_advance() {
+        for (tempo, tempo_slope) in [(120f64, 0f64), (97.3f64, 0.0004f64), (180f64, -0.0009f64)] {
+            let playhead = playhead(tempo, tempo_slope);
+            for ticks in (0..40).map(|i| i * TICKS_PER_CROTCHET / 17 - 1) {
+                let target = playhead.pos + MusicalPosition::from_ticks(ticks);
+                assert_eq!(
+                    first_sample_at(&playhead, target),
+                    playhead.first_sample_at(target, 0..SAMPLES, SAMPLE_RATE),
+                    "{} {} {}",
+                    tempo,
+                    tempo_slope,
+                    ticks
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn first_sample_at_is_within_range() {
+        let playhead = playhead(120f64, 0f64);
+        assert_eq!(
+            10,
+            playhead.first_sample_at(playhead.pos, 10..SAMPLES, SAMPLE_RATE)
+        );
+        let target = playhead.pos + MusicalPosition::from_ticks(100 * TICKS_PER_CROTCHET);
+        assert_eq!(
+            SAMPLES,
+            playhead.first_sample_at(target, 0..SAMPLES, SAMPLE_RATE)
+        );
+    }
+
+    #[test]
+    fn advance_crosses_bars() {
+        let playhead = playhead(120f64, 0f64).advance(22_050 * 2, SAMPLE_RATE);
+        assert_eq!(4, playhead.bar_number);
+        assert_eq!(
+            MusicalPosition::from_ticks(16 * TICKS_PER_CROTCHET),
+            playhead.bar_start
+        );
+        assert_eq!(MusicalPosition::from_crotchets(16.9f64), playhead.pos);
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use nih_plug::prelude::*;
+
+/// Parameters for ducking clicks based on the level of the sidechain
+#[derive(Params)]
+pub struct DuckingParams {
+    #[id = "enabled"]
+    pub enabled: BoolParam,
+
+    #[id = "threshold"]
+    pub threshold: FloatParam,
+
+    #[id = "range"]
+    pub range: FloatParam,
+
+    #[id = "attack"]
+    pub attack: FloatParam,
+
+    #[id = "release"]
+    pub release: FloatParam,
+}
+
+impl Default for DuckingParams {
+    fn default() -> Self {
+        Self {
+            enabled: BoolParam::new("Ducking", false),
+            threshold: FloatParam::new(
+                "Threshold",
+                -30f32,
+                FloatRange::Linear {
+                    min: -60f32,
+                    max: 0f32,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1f32),
+            range: FloatParam::new(
+                "Range",
+                12f32,
+                FloatRange::Linear {
+                    min: 0f32,
+                    max: 40f32,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1f32),
+            attack: FloatParam::new(
+                "Attack",
+                10f32,
+                FloatRange::Skewed {
+                    min: 0.1f32,
+                    max: 100f32,
+                    factor: FloatRange::skew_factor(-1f32),
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1f32),
+            release: FloatParam::new(
+                "Release",
+                300f32,
+                FloatRange::Skewed {
+                    min: 10f32,
+                    max: 2_000f32,
+                    factor: FloatRange::skew_factor(-1f32),
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(1f32),
+        }
+    }
+}
+
+/// Peak envelope follower with separate attack and release times
+#[derive(Default)]
+pub struct EnvelopeFollower {
+    envelope: f32,
+    attack_coefficient: f32,
+    release_coefficient: f32,
+}
+
+impl EnvelopeFollower {
+    pub fn set_times(&mut self, sample_rate: f32, attack_ms: f32, release_ms: f32) {
+        self.attack_coefficient = Self::coefficient(sample_rate, attack_ms);
+        self.release_coefficient = Self::coefficient(sample_rate, release_ms);
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let level = input.abs();
+        let coefficient = if level > self.envelope {
+            self.attack_coefficient
+        } else {
+            self.release_coefficient
+        };
+        self.envelope = level + coefficient * (self.envelope - level);
+        self.envelope
+    }
+
+    pub fn reset(&mut self) {
+        self.envelope = 0f32;
+    }
+
+    fn coefficient(sample_rate: f32, time_ms: f32) -> f32 {
+        (-1f32 / (time_ms * 0.001f32 * sample_rate)).exp()
+    }
+}
+
+/// Lowers the click level when the sidechain is quiet and restores it
+/// when the sidechain is loud
+#[derive(Default)]
+pub struct Ducker {
+    follower: EnvelopeFollower,
+
+    /// Click gain for each sample of the most recently processed block
+    gains: Vec<f32>,
+
+    is_ducking: bool,
+}
+
+impl Ducker {
+    pub fn initialize(&mut self, max_buffer_size: usize) {
+        self.gains = vec![1f32; max_buffer_size];
+        self.is_ducking = false;
+    }
+
+    /// Applies the click gain to a run of values starting at the given
+    /// sample of the most recently processed block
+    pub fn apply(&self, start: usize, values: &mut [f32]) {
+        if self.is_ducking {
+            for (value, gain) in values.iter_mut().zip(&self.gains[start..]) {
+                *value *= gain;
+            }
+        }
+    }
+
+    pub fn process(
+        &mut self,
+        params: &DuckingParams,
+        sidechain: Option<&Buffer>,
+        sample_rate: f32,
+    ) {
+        let sidechain = match sidechain {
+            Some(sidechain) if params.enabled.value() => sidechain,
+            _ => {
+                self.reset();
+                return;
+            }
+        };
+
+        self.follower
+            .set_times(sample_rate, params.attack.value(), params.release.value());
+
+        self.is_ducking = true;
+
+        // Below the threshold the click is reduced by the same amount as
+        // the sidechain, down to the maximum range
+        let threshold = params.threshold.value();
+        let range = params.range.value();
+        let channels = sidechain.as_slice_immutable();
+        for (sample_id, gain) in self.gains.iter_mut().enumerate().take(sidechain.samples()) {
+            let peak = channels
+                .iter()
+                .fold(0f32, |peak, channel| peak.max(channel[sample_id].abs()));
+            let envelope = self.follower.process(peak);
+            let reduction = (threshold - util::gain_to_db(envelope)).clamp(0f32, range);
+            *gain = util::db_to_gain(-reduction);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.follower.reset();
+        self.is_ducking = false;
+    }
+}
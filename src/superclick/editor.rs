@@ -19,21 +19,37 @@
 // OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
+use super::click::Tier;
+use super::clock::{ClockParams, ClockSource};
+use super::cue::Cue;
 use super::display::Display;
+use super::layer::LayerParams;
 use super::params::SuperClickParams;
+use super::preset::{Preset, PresetLibrary};
+use super::profile::{CustomProfile, DeviceProfile};
+use super::setlist::Setlist;
+use super::theme::{LampStyle, Palette, Theme};
+use crate::error::Error;
+use crate::music_theory::Meter;
 use crate::package::{PACKAGE_BUILD_VERSION, PACKAGE_HOME_PAGE, PACKAGE_NAME, PACKAGE_VERSION};
+use crate::result::{GetOr, Result};
 use nih_plug::nih_error;
-use nih_plug::prelude::{Editor, GuiContext};
-use nih_plug_iced::button;
+use nih_plug::prelude::{Editor, GuiContext, Param, ParamSetter};
 use nih_plug_iced::executor::Default;
+use nih_plug_iced::{button, pick_list, scrollable, text_input};
 use nih_plug_iced::{
-    create_iced_editor, Button, Color, Column, Command, Element, IcedEditor, IcedState, Text,
-    WindowQueue,
+    create_iced_editor, Button, Color, Column, Command, Container, Element, IcedEditor, IcedState,
+    Length, PickList, Row, Scrollable, Space, Subscription, Text, TextInput, WindowQueue,
+    WindowSubs,
 };
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
+const LAMP_SIZE: u16 = 24;
+
 pub fn create_default_editor_state() -> Arc<IcedState> {
-    IcedState::from_size(400, 300)
+    IcedState::from_size(400, 600)
 }
 
 pub fn create_editor(
@@ -54,11 +70,273 @@ struct SuperClickEditorInitializationFlags {
 }
 
 struct SuperClickEditor {
-    #[allow(unused)]
     params: Arc<SuperClickParams>,
     context: Arc<dyn GuiContext>,
     display: Arc<Display>,
     report_bug_button_state: button::State,
+    run_button_state: button::State,
+    tempo: String,
+    tempo_state: text_input::State,
+    theme_pick_list_state: pick_list::State<Theme>,
+    profile_path: String,
+    profile_path_state: text_input::State,
+    load_profile_button_state: button::State,
+    preset_library: Option<PresetLibrary>,
+    preset_names: Vec<String>,
+    selected_preset: Option<String>,
+    preset_pick_list_state: pick_list::State<String>,
+    preset_name: String,
+    preset_name_state: text_input::State,
+    save_preset_button_state: button::State,
+    rename_preset_button_state: button::State,
+    preset_path: String,
+    preset_path_state: text_input::State,
+    import_preset_button_state: button::State,
+    export_preset_button_state: button::State,
+    setlist_path: String,
+    setlist_path_state: text_input::State,
+    load_setlist_button_state: button::State,
+    previous_song_button_state: button::State,
+    next_song_button_state: button::State,
+    cue_bar: String,
+    cue_bar_state: text_input::State,
+    cue_label: String,
+    cue_label_state: text_input::State,
+    add_cue_button_state: button::State,
+    remove_cue_button_states: Vec<button::State>,
+    cue_sample_dir: String,
+    cue_sample_dir_state: text_input::State,
+    load_cue_samples_button_state: button::State,
+    count_sample_dir: String,
+    count_sample_dir_state: text_input::State,
+    load_count_samples_button_state: button::State,
+    scrollable_state: scrollable::State,
+    status: Option<String>,
+}
+
+impl SuperClickEditor {
+    fn set_param<P: Param>(&self, param: &P, value: P::Plain) {
+        let setter = ParamSetter::new(self.context.as_ref());
+        setter.begin_set_parameter(param);
+        setter.set_parameter(param, value);
+        setter.end_set_parameter(param);
+    }
+
+    fn clock_source(&self) -> ClockSource {
+        self.params.clock.clock_source(self.context.plugin_api())
+    }
+
+    /// Sets the tempo from beats per minute of the beat unit
+    fn set_tempo(&mut self) {
+        match self.tempo.trim().parse::<f64>() {
+            Ok(tempo) => self.set_param(
+                &self.params.clock.tempo,
+                (tempo * self.params.clock.beat_length_crotchets()) as f32,
+            ),
+            Err(_) => self.status = Some(format!("Invalid tempo \"{}\"", self.tempo.trim())),
+        }
+    }
+
+    fn set_theme(&self, theme: Theme) {
+        if let Ok(mut guard) = self.params.theme.write() {
+            *guard = theme;
+        }
+    }
+
+    fn load_profile(&mut self) {
+        match CustomProfile::load(Path::new(self.profile_path.trim())) {
+            Ok(custom_profile) => {
+                self.status = Some(format!("Loaded profile \"{}\"", custom_profile.name));
+                if let Ok(mut guard) = self.params.custom_profile.write() {
+                    *guard = Some(custom_profile);
+                }
+                self.set_param(&self.params.device_profile, DeviceProfile::Custom);
+            }
+            Err(e) => self.status = Some(format!("Failed to load profile: {:?}", e)),
+        }
+    }
+
+    fn preset_library(&self) -> Result<&PresetLibrary> {
+        self.preset_library
+            .as_ref()
+            .get_or(Error::PresetDirUnavailable)
+    }
+
+    fn refresh_preset_names(&mut self) {
+        match self.preset_library().and_then(PresetLibrary::names) {
+            Ok(names) => self.preset_names = names,
+            Err(e) => self.status = Some(format!("Failed to list presets: {:?}", e)),
+        }
+    }
+
+    fn apply_preset(&mut self, preset: Preset) {
+        preset.apply(&self.params, self.context.as_ref());
+        self.status = Some(format!("Loaded preset \"{}\"", preset.name));
+        self.selected_preset = Some(preset.name.clone());
+        self.preset_name = preset.name;
+    }
+
+    fn select_preset(&mut self, name: String) {
+        match self
+            .preset_library()
+            .and_then(|library| library.load(&name))
+        {
+            Ok(preset) => self.apply_preset(preset),
+            Err(e) => self.status = Some(format!("Failed to load preset: {:?}", e)),
+        }
+    }
+
+    fn save_preset(&mut self) {
+        let name = String::from(self.preset_name.trim());
+        let result = Preset::capture(&name, &self.params)
+            .and_then(|preset| self.preset_library()?.save(&preset));
+        match result {
+            Ok(()) => {
+                self.status = Some(format!("Saved preset \"{}\"", name));
+                self.selected_preset = Some(name);
+                self.refresh_preset_names();
+            }
+            Err(e) => self.status = Some(format!("Failed to save preset: {:?}", e)),
+        }
+    }
+
+    fn rename_preset(&mut self) {
+        let new_name = String::from(self.preset_name.trim());
+        let result = self
+            .selected_preset
+            .as_deref()
+            .get_or(Error::InvalidPresetName)
+            .and_then(|old_name| self.preset_library()?.rename(old_name, &new_name));
+        match result {
+            Ok(()) => {
+                self.status = Some(format!("Renamed preset to \"{}\"", new_name));
+                self.selected_preset = Some(new_name);
+                self.refresh_preset_names();
+            }
+            Err(e) => self.status = Some(format!("Failed to rename preset: {:?}", e)),
+        }
+    }
+
+    /// Applies the current song's preset through the host once the song has
+    /// changed, including when the change comes from MIDI
+    fn apply_song_preset(&mut self) {
+        if let Some(name) = self
+            .params
+            .pending_song_preset()
+            .and_then(|position| self.params.take_song_preset(position))
+        {
+            self.select_preset(name);
+        }
+    }
+
+    fn import_preset(&mut self) {
+        match Preset::read(Path::new(self.preset_path.trim())) {
+            Ok(preset) => self.apply_preset(preset),
+            Err(e) => self.status = Some(format!("Failed to import preset: {:?}", e)),
+        }
+    }
+
+    fn export_preset(&mut self) {
+        let result = Preset::capture(self.preset_name.trim(), &self.params)
+            .and_then(|preset| preset.write(Path::new(self.preset_path.trim())));
+        match result {
+            Ok(()) => self.status = Some(format!("Exported preset to {}", self.preset_path.trim())),
+            Err(e) => self.status = Some(format!("Failed to export preset: {:?}", e)),
+        }
+    }
+
+    fn load_setlist(&mut self) {
+        match Setlist::read(Path::new(self.setlist_path.trim())) {
+            Ok(setlist) => {
+                self.status = Some(format!("Loaded setlist of {} songs", setlist.songs.len()));
+                if let Ok(mut guard) = self.params.setlist.write() {
+                    *guard = setlist;
+                }
+                self.params.setlist_position.store(0, Ordering::Relaxed);
+                self.params.forget_applied_song_preset();
+                self.set_param(&self.params.setlist_enabled, true);
+            }
+            Err(e) => self.status = Some(format!("Failed to load setlist: {:?}", e)),
+        }
+    }
+
+    /// Description of the current song if the setlist is enabled
+    fn song_description(&self) -> Option<String> {
+        if !self.params.setlist_enabled.value() {
+            return None;
+        }
+
+        let setlist = self.params.setlist.read().ok()?;
+        let position = self.params.setlist_position.load(Ordering::Relaxed);
+        let entry = setlist.songs.get(position)?;
+        Some(format!(
+            "Song {}/{}: {}",
+            position + 1,
+            setlist.songs.len(),
+            entry.name
+        ))
+    }
+
+    fn add_cue(&mut self) {
+        let result = self
+            .cue_bar
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| Error::InvalidCueBar)
+            .and_then(|bar| match self.params.cue_track.write() {
+                Ok(mut cue_track) => cue_track.add(bar, &self.cue_label),
+                Err(_) => Ok(()),
+            });
+        match result {
+            Ok(()) => {
+                self.params.reload_cue_bank();
+                self.cue_bar.clear();
+                self.cue_label.clear();
+            }
+            Err(e) => self.status = Some(format!("Failed to add cue: {:?}", e)),
+        }
+    }
+
+    fn remove_cue(&mut self, index: usize) {
+        if let Ok(mut cue_track) = self.params.cue_track.write() {
+            cue_track.remove(index);
+        }
+        self.params.reload_cue_bank();
+    }
+
+    fn load_cue_samples(&mut self) {
+        let sample_dir = self.cue_sample_dir.trim();
+        if let Ok(mut cue_track) = self.params.cue_track.write() {
+            cue_track.sample_dir = if sample_dir.is_empty() {
+                None
+            } else {
+                Some(PathBuf::from(sample_dir))
+            };
+        }
+        self.params.reload_cue_bank();
+        self.status = Some(String::from("Reloaded cue recordings"));
+    }
+
+    fn load_count_samples(&mut self) {
+        let sample_dir = self.count_sample_dir.trim();
+        if let Ok(mut count_sample_dir) = self.params.count_sample_dir.write() {
+            *count_sample_dir = if sample_dir.is_empty() {
+                None
+            } else {
+                Some(PathBuf::from(sample_dir))
+            };
+        }
+        self.params.reload_count_bank();
+        self.status = Some(String::from("Reloaded count recordings"));
+    }
+
+    fn cues(&self) -> Vec<Cue> {
+        self.params
+            .cue_track
+            .read()
+            .map(|cue_track| cue_track.cues.clone())
+            .unwrap_or_default()
+    }
 }
 
 impl IcedEditor for SuperClickEditor {
@@ -70,13 +348,76 @@ impl IcedEditor for SuperClickEditor {
         initialization_flags: Self::InitializationFlags,
         context: Arc<dyn GuiContext>,
     ) -> (Self, Command<Self::Message>) {
-        let editor = SuperClickEditor {
+        let mut editor = SuperClickEditor {
             params: initialization_flags.params,
             context,
             display: initialization_flags.display,
             report_bug_button_state: button::State::default(),
+            run_button_state: button::State::default(),
+            tempo: String::new(),
+            tempo_state: text_input::State::default(),
+            theme_pick_list_state: pick_list::State::default(),
+            profile_path: String::new(),
+            profile_path_state: text_input::State::default(),
+            load_profile_button_state: button::State::default(),
+            preset_library: None,
+            preset_names: Vec::new(),
+            selected_preset: None,
+            preset_pick_list_state: pick_list::State::default(),
+            preset_name: String::new(),
+            preset_name_state: text_input::State::default(),
+            save_preset_button_state: button::State::default(),
+            rename_preset_button_state: button::State::default(),
+            preset_path: String::new(),
+            preset_path_state: text_input::State::default(),
+            import_preset_button_state: button::State::default(),
+            export_preset_button_state: button::State::default(),
+            setlist_path: String::new(),
+            setlist_path_state: text_input::State::default(),
+            load_setlist_button_state: button::State::default(),
+            previous_song_button_state: button::State::default(),
+            next_song_button_state: button::State::default(),
+            cue_bar: String::new(),
+            cue_bar_state: text_input::State::default(),
+            cue_label: String::new(),
+            cue_label_state: text_input::State::default(),
+            add_cue_button_state: button::State::default(),
+            remove_cue_button_states: Vec::new(),
+            cue_sample_dir: String::new(),
+            cue_sample_dir_state: text_input::State::default(),
+            load_cue_samples_button_state: button::State::default(),
+            count_sample_dir: String::new(),
+            count_sample_dir_state: text_input::State::default(),
+            load_count_samples_button_state: button::State::default(),
+            scrollable_state: scrollable::State::default(),
+            status: None,
         };
 
+        editor.tempo = format!(
+            "{:.1}",
+            editor.params.clock.tempo.value() as f64 / editor.params.clock.beat_length_crotchets()
+        );
+
+        if let Ok(cue_track) = editor.params.cue_track.read() {
+            if let Some(ref sample_dir) = cue_track.sample_dir {
+                editor.cue_sample_dir = sample_dir.display().to_string();
+            }
+        }
+
+        if let Ok(count_sample_dir) = editor.params.count_sample_dir.read() {
+            if let Some(ref sample_dir) = *count_sample_dir {
+                editor.count_sample_dir = sample_dir.display().to_string();
+            }
+        }
+
+        match PresetLibrary::open() {
+            Ok(library) => {
+                editor.preset_library = Some(library);
+                editor.refresh_preset_names();
+            }
+            Err(e) => editor.status = Some(format!("Presets unavailable: {:?}", e)),
+        }
+
         (editor, Command::none())
     }
 
@@ -90,65 +431,466 @@ impl IcedEditor for SuperClickEditor {
         message: Self::Message,
     ) -> Command<Self::Message> {
         match message {
+            Self::Message::Frame => self.apply_song_preset(),
             Self::Message::ReportBugButtonPressed => {
                 if let Err(e) = webbrowser::open(PACKAGE_HOME_PAGE) {
                     nih_error!("{}", e);
                 }
             }
+            Self::Message::RunButtonPressed => {
+                self.set_param(&self.params.clock.run, !self.params.clock.run.value())
+            }
+            Self::Message::TempoChanged(tempo) => self.tempo = tempo,
+            Self::Message::TempoSubmitted => self.set_tempo(),
+            Self::Message::ThemeSelected(theme) => self.set_theme(theme),
+            Self::Message::ProfilePathChanged(path) => self.profile_path = path,
+            Self::Message::LoadProfileButtonPressed => self.load_profile(),
+            Self::Message::PresetSelected(name) => self.select_preset(name),
+            Self::Message::PresetNameChanged(name) => self.preset_name = name,
+            Self::Message::SavePresetButtonPressed => self.save_preset(),
+            Self::Message::RenamePresetButtonPressed => self.rename_preset(),
+            Self::Message::PresetPathChanged(path) => self.preset_path = path,
+            Self::Message::ImportPresetButtonPressed => self.import_preset(),
+            Self::Message::ExportPresetButtonPressed => self.export_preset(),
+            Self::Message::SetlistPathChanged(path) => self.setlist_path = path,
+            Self::Message::LoadSetlistButtonPressed => self.load_setlist(),
+            Self::Message::PreviousSongButtonPressed => self.params.step_setlist(false),
+            Self::Message::NextSongButtonPressed => self.params.step_setlist(true),
+            Self::Message::CueBarChanged(bar) => self.cue_bar = bar,
+            Self::Message::CueLabelChanged(label) => self.cue_label = label,
+            Self::Message::AddCueButtonPressed => self.add_cue(),
+            Self::Message::RemoveCueButtonPressed(index) => self.remove_cue(index),
+            Self::Message::CueSampleDirChanged(sample_dir) => self.cue_sample_dir = sample_dir,
+            Self::Message::LoadCueSamplesButtonPressed => self.load_cue_samples(),
+            Self::Message::CountSampleDirChanged(sample_dir) => self.count_sample_dir = sample_dir,
+            Self::Message::LoadCountSamplesButtonPressed => self.load_count_samples(),
         }
         Command::none()
     }
 
+    fn subscription(
+        &self,
+        window_subs: &mut WindowSubs<Self::Message>,
+    ) -> Subscription<Self::Message> {
+        window_subs.on_frame = Some(Message::Frame);
+        Subscription::none()
+    }
+
     fn view(&mut self) -> Element<'_, Self::Message> {
-        let strs = DisplayStrings::new(&self.params.editor_state, &self.display);
+        let clock_source = self.clock_source();
+        let strs = DisplayStrings::new(
+            &self.params.editor_state,
+            &self.display,
+            &self.params.clock,
+            clock_source,
+        );
+        let theme = self.params.theme();
+        let palette = theme.palette();
 
-        let mut column = Column::new().push(Text::new(strs.title));
+        let mut column = Column::new().push(Text::new(strs.title).color(palette.text));
 
         if let Some(ref s) = strs.error {
-            column = column.push(Text::new(s)).push(
+            column = column.push(Text::new(s).color(palette.error)).push(
                 Button::new(&mut self.report_bug_button_state, Text::new("Report bug"))
                     .on_press(Self::Message::ReportBugButtonPressed),
             );
         }
 
-        column = column.push(Text::new(&strs.song_position));
+        // Hosts start and stop the internal clock with the Run parameter but
+        // the standalone application has no other controls
+        if clock_source == ClockSource::Internal {
+            column = column.push(
+                Row::new()
+                    .spacing(8)
+                    .push(
+                        Button::new(
+                            &mut self.run_button_state,
+                            Text::new(if self.params.clock.run.value() {
+                                "Stop"
+                            } else {
+                                "Start"
+                            }),
+                        )
+                        .on_press(Message::RunButtonPressed),
+                    )
+                    .push(Text::new("Tempo").color(palette.text))
+                    .push(
+                        TextInput::new(
+                            &mut self.tempo_state,
+                            "Beats per minute",
+                            &self.tempo,
+                            Message::TempoChanged,
+                        )
+                        .on_submit(Message::TempoSubmitted)
+                        .padding(4),
+                    ),
+            );
+        }
+
+        if let Some(s) = self.song_description() {
+            column = column.push(
+                Row::new()
+                    .spacing(8)
+                    .push(
+                        Button::new(&mut self.previous_song_button_state, Text::new("<"))
+                            .on_press(Message::PreviousSongButtonPressed),
+                    )
+                    .push(
+                        Button::new(&mut self.next_song_button_state, Text::new(">"))
+                            .on_press(Message::NextSongButtonPressed),
+                    )
+                    .push(Text::new(s).color(palette.text)),
+            );
+        }
+
+        column = column.push(Text::new(&strs.song_position).color(palette.text));
+
+        if self.display.is_playing() && self.params.gap.is_muted(self.display.bar_number()) {
+            column = column.push(Text::new("Click muted: keep time!").color(palette.text));
+        }
 
         if let Some(ref s) = strs.tempo {
-            column = column.push(Text::new(s))
+            column = column.push(Text::new(s).color(palette.text))
+        }
+
+        if let Some(ref s) = strs.trainer {
+            column = column.push(Text::new(s).color(palette.text))
+        }
+
+        if let Some(lamps) = beat_lamps(&self.display, &self.params.clock, &palette) {
+            column = column.push(lamps);
+        }
+
+        if let Some(lamps) = layer_lamps(&self.display, &self.params.layer, &palette) {
+            column = column.push(lamps);
         }
 
         if let Some(ref s) = strs.big {
-            column = column.push(Text::new(s).size(150));
+            column = column.push(Text::new(s).size(150).color(palette.text));
+        }
+
+        column = column.push(
+            Row::new()
+                .spacing(8)
+                .push(Text::new("Theme").color(palette.text))
+                .push(PickList::new(
+                    &mut self.theme_pick_list_state,
+                    &Theme::ALL[..],
+                    Some(theme),
+                    Message::ThemeSelected,
+                )),
+        );
+
+        column = column.push(
+            Row::new()
+                .spacing(8)
+                .push(Text::new("Profile").color(palette.text))
+                .push(
+                    TextInput::new(
+                        &mut self.profile_path_state,
+                        "Path to .toml or .json file",
+                        &self.profile_path,
+                        Message::ProfilePathChanged,
+                    )
+                    .padding(4),
+                )
+                .push(
+                    Button::new(&mut self.load_profile_button_state, Text::new("Load"))
+                        .on_press(Message::LoadProfileButtonPressed),
+                ),
+        );
+
+        column = column
+            .push(
+                Row::new()
+                    .spacing(8)
+                    .push(Text::new("Preset").color(palette.text))
+                    .push(PickList::new(
+                        &mut self.preset_pick_list_state,
+                        &self.preset_names[..],
+                        self.selected_preset.clone(),
+                        Message::PresetSelected,
+                    )),
+            )
+            .push(
+                Row::new()
+                    .spacing(8)
+                    .push(
+                        TextInput::new(
+                            &mut self.preset_name_state,
+                            "Preset name",
+                            &self.preset_name,
+                            Message::PresetNameChanged,
+                        )
+                        .padding(4),
+                    )
+                    .push(
+                        Button::new(&mut self.save_preset_button_state, Text::new("Save"))
+                            .on_press(Message::SavePresetButtonPressed),
+                    )
+                    .push(
+                        Button::new(&mut self.rename_preset_button_state, Text::new("Rename"))
+                            .on_press(Message::RenamePresetButtonPressed),
+                    ),
+            )
+            .push(
+                Row::new()
+                    .spacing(8)
+                    .push(
+                        TextInput::new(
+                            &mut self.preset_path_state,
+                            "Path to .json file",
+                            &self.preset_path,
+                            Message::PresetPathChanged,
+                        )
+                        .padding(4),
+                    )
+                    .push(
+                        Button::new(&mut self.import_preset_button_state, Text::new("Import"))
+                            .on_press(Message::ImportPresetButtonPressed),
+                    )
+                    .push(
+                        Button::new(&mut self.export_preset_button_state, Text::new("Export"))
+                            .on_press(Message::ExportPresetButtonPressed),
+                    ),
+            );
+
+        column = column.push(
+            Row::new()
+                .spacing(8)
+                .push(Text::new("Setlist").color(palette.text))
+                .push(
+                    TextInput::new(
+                        &mut self.setlist_path_state,
+                        "Path to .toml file",
+                        &self.setlist_path,
+                        Message::SetlistPathChanged,
+                    )
+                    .padding(4),
+                )
+                .push(
+                    Button::new(&mut self.load_setlist_button_state, Text::new("Load"))
+                        .on_press(Message::LoadSetlistButtonPressed),
+                ),
+        );
+
+        let cues = self.cues();
+        self.remove_cue_button_states
+            .resize_with(cues.len(), button::State::default);
+        for (index, (cue, state)) in cues
+            .iter()
+            .zip(self.remove_cue_button_states.iter_mut())
+            .enumerate()
+        {
+            column = column.push(
+                Row::new()
+                    .spacing(8)
+                    .push(
+                        Button::new(state, Text::new("x"))
+                            .on_press(Message::RemoveCueButtonPressed(index)),
+                    )
+                    .push(
+                        Text::new(format!("Cue at bar {}: {}", cue.bar, cue.label))
+                            .color(palette.text),
+                    ),
+            );
+        }
+
+        column = column
+            .push(
+                Row::new()
+                    .spacing(8)
+                    .push(Text::new("Cue").color(palette.text))
+                    .push(
+                        TextInput::new(
+                            &mut self.cue_bar_state,
+                            "Bar",
+                            &self.cue_bar,
+                            Message::CueBarChanged,
+                        )
+                        .width(Length::Units(60))
+                        .padding(4),
+                    )
+                    .push(
+                        TextInput::new(
+                            &mut self.cue_label_state,
+                            "Label",
+                            &self.cue_label,
+                            Message::CueLabelChanged,
+                        )
+                        .padding(4),
+                    )
+                    .push(
+                        Button::new(&mut self.add_cue_button_state, Text::new("Add"))
+                            .on_press(Message::AddCueButtonPressed),
+                    ),
+            )
+            .push(
+                Row::new()
+                    .spacing(8)
+                    .push(Text::new("Cue recordings").color(palette.text))
+                    .push(
+                        TextInput::new(
+                            &mut self.cue_sample_dir_state,
+                            "Directory of .wav files",
+                            &self.cue_sample_dir,
+                            Message::CueSampleDirChanged,
+                        )
+                        .padding(4),
+                    )
+                    .push(
+                        Button::new(&mut self.load_cue_samples_button_state, Text::new("Load"))
+                            .on_press(Message::LoadCueSamplesButtonPressed),
+                    ),
+            )
+            .push(
+                Row::new()
+                    .spacing(8)
+                    .push(Text::new("Count recordings").color(palette.text))
+                    .push(
+                        TextInput::new(
+                            &mut self.count_sample_dir_state,
+                            "Directory of .wav files",
+                            &self.count_sample_dir,
+                            Message::CountSampleDirChanged,
+                        )
+                        .padding(4),
+                    )
+                    .push(
+                        Button::new(&mut self.load_count_samples_button_state, Text::new("Load"))
+                            .on_press(Message::LoadCountSamplesButtonPressed),
+                    ),
+            );
+
+        if let Some(ref s) = self.status {
+            column = column.push(Text::new(s).color(palette.text));
         }
 
-        column.into()
+        Scrollable::new(&mut self.scrollable_state)
+            .push(column)
+            .into()
     }
 
     fn background_color(&self) -> Color {
-        Color {
-            r: 0.58,
-            g: 0.98,
-            b: 0.58,
-            a: 1.0,
-        }
+        self.params.theme().palette().background
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 enum Message {
+    Frame,
     ReportBugButtonPressed,
+    RunButtonPressed,
+    TempoChanged(String),
+    TempoSubmitted,
+    ThemeSelected(Theme),
+    ProfilePathChanged(String),
+    LoadProfileButtonPressed,
+    PresetSelected(String),
+    PresetNameChanged(String),
+    SavePresetButtonPressed,
+    RenamePresetButtonPressed,
+    PresetPathChanged(String),
+    ImportPresetButtonPressed,
+    ExportPresetButtonPressed,
+    SetlistPathChanged(String),
+    LoadSetlistButtonPressed,
+    PreviousSongButtonPressed,
+    NextSongButtonPressed,
+    CueBarChanged(String),
+    CueLabelChanged(String),
+    AddCueButtonPressed,
+    RemoveCueButtonPressed(usize),
+    CueSampleDirChanged(String),
+    LoadCueSamplesButtonPressed,
+    CountSampleDirChanged(String),
+    LoadCountSamplesButtonPressed,
+}
+
+/// One lamp per beat in the bar with the current beat lit
+fn beat_lamps<'a>(
+    display: &Display,
+    clock: &ClockParams,
+    palette: &Palette,
+) -> Option<Element<'a, Message>> {
+    if !display.is_playing() {
+        return None;
+    }
+
+    let meter = clock.beat_unit.value().apply(display.meter()?);
+    let current = display.bar_beat_tick(&meter).beat;
+
+    let mut row = Row::new().spacing(4);
+    for i in 0..meter.beat_count() {
+        let color = if i == current {
+            palette.lamp(Tier::for_beat(&meter, i))
+        } else {
+            palette.lamp_off
+        };
+        row = row.push(
+            Container::new(Space::new(
+                Length::Units(LAMP_SIZE),
+                Length::Units(LAMP_SIZE),
+            ))
+            .style(LampStyle {
+                color,
+                border: palette.text,
+            }),
+        );
+    }
+
+    Some(row.into())
+}
+
+fn layer_lamps<'a>(
+    display: &Display,
+    layer: &LayerParams,
+    palette: &Palette,
+) -> Option<Element<'a, Message>> {
+    if !display.is_playing() {
+        return None;
+    }
+
+    let beat = layer.beat_at(display.pos(), display.bar_start(), &display.meter()?)?;
+
+    let mut row = Row::new().spacing(4);
+    for i in 0..beat.count {
+        let color = if i == beat.index {
+            palette.lamp(beat.tier())
+        } else {
+            palette.lamp_off
+        };
+        row = row.push(
+            Container::new(Space::new(
+                Length::Units(LAMP_SIZE / 2),
+                Length::Units(LAMP_SIZE / 2),
+            ))
+            .style(LampStyle {
+                color,
+                border: palette.text,
+            }),
+        );
+    }
+
+    Some(row.into())
 }
 
 struct DisplayStrings {
     title: String,
     song_position: String,
     tempo: Option<String>,
+    trainer: Option<String>,
     big: Option<String>,
     error: Option<String>,
 }
 
 impl DisplayStrings {
-    fn new(editor_state: &IcedState, display: &Display) -> Self {
+    fn new(
+        editor_state: &IcedState,
+        display: &Display,
+        clock: &ClockParams,
+        clock_source: ClockSource,
+    ) -> Self {
         let title = Self::format_title(editor_state);
 
         let error_code = display.error_code();
@@ -158,47 +900,89 @@ impl DisplayStrings {
             Some(format!("Error: {}", error_code))
         };
 
-        match (
-            display.is_playing(),
-            display.time_signature_top(),
-            display.time_signature_bottom(),
-        ) {
-            (true, Some(time_signature_top), Some(time_signature_bottom)) => Self {
-                title,
-                song_position: format!(
-                    "Song position: {:04}/{:05.2}/{:05.2}",
-                    display.bar_number(),
-                    display.bar_start_pos_crotchets(),
-                    display.pos_crotchets(),
-                ),
-                tempo: Some(format!(
-                    "Tempo: {:.1} qpm / {:.1} bpm",
-                    display.tempo(),
-                    display.tempo() * time_signature_bottom.as_number() as f64
-                        / (4 * time_signature_top.basis()) as f64
-                )),
-                big: Some(format!(
-                    "{} of {}/{}",
-                    ((display.pos_crotchets() - display.bar_start_pos_crotchets())
-                        * time_signature_bottom.as_number() as f64
-                        / 4f64)
-                        .trunc() as i32
-                        + 1,
-                    time_signature_top,
-                    time_signature_bottom,
-                )),
-                error,
-            },
+        match (display.is_playing(), display.meter()) {
+            (true, Some(meter)) => {
+                let meter = clock.beat_unit.value().apply(meter);
+                Self {
+                    title,
+                    song_position: if display.bar_number() < 0 {
+                        format!("Count-in: {} bar(s) to go", -display.bar_number())
+                    } else {
+                        format!(
+                            "Song position: {:04}/{:05.2}/{:05.2}",
+                            display.bar_number(),
+                            display.bar_start().crotchets(),
+                            display.pos().crotchets(),
+                        )
+                    },
+                    tempo: Some(format!(
+                        "Tempo: {}",
+                        Self::format_tempo(display.tempo(), &meter)
+                    )),
+                    trainer: Self::format_trainer(
+                        clock,
+                        clock_source,
+                        display.bar_number(),
+                        &meter,
+                    ),
+                    big: Some(format!(
+                        "{} of {}/{}",
+                        display.bar_beat_tick(&meter).beat + 1,
+                        meter.time_signature_top(),
+                        meter.time_signature_bottom(),
+                    )),
+                    error,
+                }
+            }
             _ => Self {
                 title,
                 song_position: String::from("(Idle)"),
                 tempo: None,
+                trainer: None,
                 big: None,
                 error,
             },
         }
     }
 
+    /// Formats a tempo in crotchets per minute together with the tempo in
+    /// the beat unit
+    fn format_tempo(tempo: f64, meter: &Meter) -> String {
+        format!(
+            "{:.1} qpm / {:.1} bpm ({})",
+            tempo,
+            tempo / meter.beat_length_crotchets(),
+            meter.beat_unit()
+        )
+    }
+
+    fn format_trainer(
+        clock: &ClockParams,
+        clock_source: ClockSource,
+        bar_number: i32,
+        meter: &Meter,
+    ) -> Option<String> {
+        if clock_source != ClockSource::Internal {
+            return None;
+        }
+
+        let progress = clock.trainer.trainer()?.progress(bar_number);
+        Some(match progress.next_tempo {
+            Some(next_tempo) => format!(
+                "Speed trainer: step {} of {}, {} in {} bar(s)",
+                progress.step + 1,
+                progress.step_count,
+                Self::format_tempo(next_tempo, meter),
+                progress.bars_to_next
+            ),
+            None => format!(
+                "Speed trainer: step {} of {}, finished",
+                progress.step + 1,
+                progress.step_count
+            ),
+        })
+    }
+
     #[cfg(debug_assertions)]
     fn format_title(editor_state: &IcedState) -> String {
         match PACKAGE_BUILD_VERSION {
@@ -0,0 +1,120 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use nih_plug::prelude::*;
+
+/// How bars are chosen to be silent for timing practice
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum GapMode {
+    /// Every bar clicks
+    Off,
+
+    /// A fixed number of clicking bars followed by a fixed number of silent
+    /// bars
+    Pattern,
+
+    /// Bars are silent at random with a given probability
+    Random,
+}
+
+#[derive(Params)]
+pub struct GapParams {
+    #[id = "mode"]
+    pub mode: EnumParam<GapMode>,
+
+    #[id = "play-bars"]
+    pub play_bars: IntParam,
+
+    #[id = "mute-bars"]
+    pub mute_bars: IntParam,
+
+    #[id = "probability"]
+    pub probability: FloatParam,
+
+    #[id = "seed"]
+    pub seed: IntParam,
+}
+
+impl Default for GapParams {
+    fn default() -> Self {
+        Self {
+            mode: EnumParam::new("Gap mode", GapMode::Off),
+            play_bars: IntParam::new("Play bars", 2, IntRange::Linear { min: 1, max: 16 })
+                .with_unit(" bars"),
+            mute_bars: IntParam::new("Mute bars", 2, IntRange::Linear { min: 1, max: 16 })
+                .with_unit(" bars"),
+            probability: FloatParam::new(
+                "Mute probability",
+                0.25f32,
+                FloatRange::Linear {
+                    min: 0f32,
+                    max: 1f32,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            seed: IntParam::new("Seed", 1, IntRange::Linear { min: 0, max: 9999 }),
+        }
+    }
+}
+
+impl GapParams {
+    /// Whether clicks are silent during the given bar
+    ///
+    /// Depends only on the bar number and parameters so that exercises
+    /// repeat exactly: count-in bars always click
+    pub fn is_muted(&self, bar_number: i32) -> bool {
+        if bar_number < 0 {
+            return false;
+        }
+
+        match self.mode.value() {
+            GapMode::Off => false,
+            GapMode::Pattern => {
+                let play_bars = self.play_bars.value();
+                bar_number % (play_bars + self.mute_bars.value()) >= play_bars
+            }
+            GapMode::Random => {
+                // Always start with a clicking bar to establish the tempo
+                bar_number > 0
+                    && unit_interval(hash(self.seed.value() as u64, bar_number as u64))
+                        < self.probability.value() as f64
+            }
+        }
+    }
+}
+
+/// SplitMix64-style hash of a seed and a value
+fn hash(seed: u64, value: u64) -> u64 {
+    let mut z = seed
+        .wrapping_mul(0x9e3779b97f4a7c15)
+        .wrapping_add(value)
+        .wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Maps a hash to the range [0, 1)
+fn unit_interval(value: u64) -> f64 {
+    (value >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,194 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::click::{Channel, Click, Tier, Waveform};
+use super::clock::Denominator;
+use crate::music_theory::{Meter, MusicalPosition, TICKS_PER_CROTCHET};
+use nih_plug::prelude::*;
+
+const CLICK_LENGTH_CROTCHETS: f64 = 0.0625;
+
+/// How the layer's beats relate to the main bar
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum LayerMode {
+    /// Beats spaced evenly across the main bar (e.g. 3 against 4)
+    Polyrhythm,
+
+    /// Pattern of beats of the layer's note value looping independently of
+    /// the main bar (e.g. 5/8 against 4/4)
+    Polymeter,
+}
+
+/// Second rhythmic layer played against the main click
+#[derive(Params)]
+pub struct LayerParams {
+    #[id = "enabled"]
+    pub enabled: BoolParam,
+
+    #[id = "mode"]
+    pub mode: EnumParam<LayerMode>,
+
+    #[id = "beats"]
+    pub beats: IntParam,
+
+    #[id = "note-value"]
+    pub note_value: EnumParam<Denominator>,
+
+    #[id = "frequency"]
+    pub frequency: FloatParam,
+}
+
+impl Default for LayerParams {
+    fn default() -> Self {
+        Self {
+            enabled: BoolParam::new("Layer", false),
+            mode: EnumParam::new("Layer mode", LayerMode::Polyrhythm),
+            beats: IntParam::new("Layer beats", 3, IntRange::Linear { min: 1, max: 32 }),
+            note_value: EnumParam::new("Layer note value", Denominator::Eight),
+            frequency: FloatParam::new(
+                "Layer frequency",
+                1200f32,
+                FloatRange::Skewed {
+                    min: 100f32,
+                    max: 8000f32,
+                    factor: FloatRange::skew_factor(-1f32),
+                },
+            )
+            .with_unit(" Hz")
+            .with_step_size(1f32),
+        }
+    }
+}
+
+/// Position within the layer's pattern
+#[derive(Clone, Copy, Debug)]
+pub struct LayerBeat {
+    pub index: i32,
+    pub count: i32,
+    pub pos_in_beat_crotchets: f64,
+}
+
+impl LayerBeat {
+    pub fn tier(&self) -> Tier {
+        if self.index == 0 {
+            Tier::Accent
+        } else {
+            Tier::Normal
+        }
+    }
+
+    pub fn is_sounding(&self) -> bool {
+        self.pos_in_beat_crotchets <= CLICK_LENGTH_CROTCHETS
+    }
+
+    /// Distance from the position to where the click has finished
+    pub fn remaining(&self) -> MusicalPosition {
+        MusicalPosition::from_ticks(
+            ((CLICK_LENGTH_CROTCHETS - self.pos_in_beat_crotchets) * TICKS_PER_CROTCHET as f64)
+                .floor() as i64
+                + 1,
+        )
+    }
+}
+
+impl LayerParams {
+    /// Click for a beat of the given tier: the first beat of the pattern is
+    /// pitched up a fifth
+    pub fn click(&self, tier: Tier) -> Click {
+        let frequency = self.frequency.value();
+        Click {
+            channel: Channel::Both,
+            waveform: Waveform::Sine,
+            frequency: if tier == Tier::Accent {
+                frequency * 1.5f32
+            } else {
+                frequency
+            },
+            length: CLICK_LENGTH_CROTCHETS,
+            level: 1f32,
+        }
+    }
+
+    /// Layer beat at the given position if the layer is enabled
+    ///
+    /// Positions are derived from the song position rather than counted so
+    /// that the layer stays locked to the host
+    pub fn beat_at(
+        &self,
+        pos: MusicalPosition,
+        bar_start: MusicalPosition,
+        meter: &Meter,
+    ) -> Option<LayerBeat> {
+        if !self.enabled.value() {
+            return None;
+        }
+
+        let count = self.beats.value();
+        let (pattern_ticks, offset_ticks) = self.pattern(pos, bar_start, meter);
+
+        // Beats may not be a whole number of ticks so the index is found
+        // without dividing the pattern
+        let index = (offset_ticks * count as i64).div_euclid(pattern_ticks) as i32;
+        if (0..count).contains(&index) {
+            let beat_start_ticks = index as f64 * pattern_ticks as f64 / count as f64;
+            Some(LayerBeat {
+                index,
+                count,
+                pos_in_beat_crotchets: (offset_ticks as f64 - beat_start_ticks)
+                    / TICKS_PER_CROTCHET as f64,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Start of the layer's next beat after the given position
+    pub fn next_beat(
+        &self,
+        pos: MusicalPosition,
+        bar_start: MusicalPosition,
+        meter: &Meter,
+    ) -> MusicalPosition {
+        let count = self.beats.value() as i64;
+        let (pattern_ticks, offset_ticks) = self.pattern(pos, bar_start, meter);
+        let index = (offset_ticks * count).div_euclid(pattern_ticks);
+        let next_offset_ticks = ((index + 1) * pattern_ticks + count - 1).div_euclid(count);
+        pos + MusicalPosition::from_ticks(next_offset_ticks - offset_ticks)
+    }
+
+    /// Length of the layer's pattern and the position's offset into it in
+    /// ticks
+    fn pattern(
+        &self,
+        pos: MusicalPosition,
+        bar_start: MusicalPosition,
+        meter: &Meter,
+    ) -> (i64, i64) {
+        match self.mode.value() {
+            LayerMode::Polyrhythm => (meter.bar_length().ticks(), (pos - bar_start).ticks()),
+            LayerMode::Polymeter => {
+                let pattern_ticks = 4 * TICKS_PER_CROTCHET * self.beats.value() as i64
+                    / self.note_value.value().as_number() as i64;
+                (pattern_ticks, pos.ticks().rem_euclid(pattern_ticks))
+            }
+        }
+    }
+}
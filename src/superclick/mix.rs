@@ -0,0 +1,109 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::routing::{ChannelGains, MAX_CHANNELS};
+use nih_plug::prelude::Enum;
+use std::ops::Range;
+
+/// How clicks are combined with the input signal on the main output
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum MixMode {
+    /// Clicks replace the input on the channels they're routed to
+    Replace,
+
+    /// Clicks are mixed over the input according to the dry/wet control
+    Mix,
+
+    /// Input is muted and only clicks are output
+    #[name = "Click only"]
+    ClickOnly,
+}
+
+impl MixMode {
+    /// Gain applied to the input signal
+    pub fn dry_gain(&self, dry_wet: f32) -> f32 {
+        match self {
+            Self::Replace => 1f32,
+            Self::Mix => (2f32 * (1f32 - dry_wet)).min(1f32),
+            Self::ClickOnly => 0f32,
+        }
+    }
+
+    /// Gain applied to the click signal
+    pub fn wet_gain(&self, dry_wet: f32) -> f32 {
+        match self {
+            Self::Mix => (2f32 * dry_wet).min(1f32),
+            Self::Replace | Self::ClickOnly => 1f32,
+        }
+    }
+
+    /// Whether the input is replaced on the channels that clicks sound on
+    pub fn replaces_input(&self) -> bool {
+        matches!(self, Self::Replace)
+    }
+}
+
+/// Samples of each main output channel whose input a click has replaced in
+/// the current block
+#[derive(Default)]
+pub struct ReplacedInput([Vec<bool>; MAX_CHANNELS]);
+
+impl ReplacedInput {
+    pub fn initialize(&mut self, max_buffer_size: usize) {
+        for replaced in self.0.iter_mut() {
+            *replaced = vec![false; max_buffer_size];
+        }
+    }
+
+    /// Restores the input for a new block
+    pub fn reset(&mut self, samples: usize) {
+        for replaced in self.0.iter_mut() {
+            replaced[..samples].fill(false);
+        }
+    }
+
+    /// Silences the input on each channel with a gain over the given samples
+    /// unless a click has already replaced it
+    pub fn replace(
+        &mut self,
+        channels: &mut [&mut [f32]],
+        gains: &ChannelGains,
+        range: Range<usize>,
+    ) {
+        for (channel_id, (channel, replaced)) in
+            channels.iter_mut().zip(self.0.iter_mut()).enumerate()
+        {
+            if gains.get(channel_id).is_none() {
+                continue;
+            }
+
+            for (sample, replaced) in channel[range.clone()]
+                .iter_mut()
+                .zip(&mut replaced[range.clone()])
+            {
+                if !*replaced {
+                    *sample = 0f32;
+                    *replaced = true;
+                }
+            }
+        }
+    }
+}
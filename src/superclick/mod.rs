@@ -19,10 +19,31 @@
 // OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
+#[cfg(feature = "bench")]
+pub mod bench;
 mod click;
+mod clock;
+mod count;
+mod cue;
 mod display;
+mod ducker;
 mod editor;
+mod gap;
+mod layer;
+mod mix;
+mod oscillator;
 mod params;
 mod plugin;
+mod preset;
+mod profile;
+mod profile_file;
+mod routing;
+mod sample;
+mod setlist;
+mod theme;
+mod timing;
+mod trainer;
 
 pub use self::plugin::SuperClick;
+
+pub(crate) use self::click::Tier;
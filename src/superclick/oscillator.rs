@@ -0,0 +1,249 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::click::{Click, Waveform};
+use nih_plug::prelude::*;
+
+/// How click tones are synthesized
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum OscillatorQuality {
+    /// Naive waveforms that alias audibly at high frequencies
+    Draft,
+
+    /// PolyBLEP waveforms with the aliasing of their discontinuities
+    /// suppressed
+    #[name = "Band-limited"]
+    BandLimited,
+}
+
+/// Oscillator generating click tones
+///
+/// Each beat's tone starts at phase zero and the phase is computed in double
+/// precision from the samples played since then rather than accumulated so
+/// that tones are identical however long the session
+#[derive(Default)]
+pub struct Oscillator {
+    sample_rate: f64,
+    beat: Option<(i32, i32)>,
+    samples: u64,
+}
+
+impl Oscillator {
+    pub fn initialize(&mut self, sample_rate: f32) {
+        *self = Self {
+            sample_rate: sample_rate as f64,
+            ..Self::default()
+        };
+    }
+
+    /// Restarts the tone on entering a new beat
+    pub fn update(&mut self, bar_number: i32, index: i32) {
+        if self.beat != Some((bar_number, index)) {
+            self.beat = Some((bar_number, index));
+            self.samples = 0;
+        }
+    }
+
+    /// Renders the click's tone into the output
+    pub fn render(&mut self, click: &Click, quality: OscillatorQuality, output: &mut [f32]) {
+        for value in output.iter_mut() {
+            *value = self.next(click, quality);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.beat = None;
+        self.samples = 0;
+    }
+
+    fn next(&mut self, click: &Click, quality: OscillatorQuality) -> f32 {
+        let cycles_per_sample = click.frequency as f64 / self.sample_rate;
+        let phase = (self.samples as f64 * cycles_per_sample).fract() as f32;
+        let phase_delta = cycles_per_sample as f32;
+        self.samples += 1;
+
+        match quality {
+            OscillatorQuality::Draft => click.waveform.value_at(phase),
+            OscillatorQuality::BandLimited => {
+                band_limited_value_at(click.waveform, phase, phase_delta)
+            }
+        }
+    }
+}
+
+/// Value of the waveform at the given phase with the discontinuities in its
+/// value and slope smoothed over the neighbouring samples
+fn band_limited_value_at(waveform: Waveform, phase: f32, phase_delta: f32) -> f32 {
+    let value = waveform.value_at(phase);
+    match waveform {
+        Waveform::Sine => value,
+        Waveform::Square => {
+            value + poly_blep(phase, phase_delta)
+                - poly_blep(wrap_phase(phase + 0.5f32), phase_delta)
+        }
+        Waveform::Saw => value - poly_blep(phase, phase_delta),
+        Waveform::Triangle => {
+            value
+                + 8f32
+                    * phase_delta
+                    * (poly_blamp(wrap_phase(phase + 0.5f32), phase_delta)
+                        - poly_blamp(phase, phase_delta))
+        }
+    }
+}
+
+/// Residual of a band-limited step of two at phase zero
+fn poly_blep(phase: f32, phase_delta: f32) -> f32 {
+    if phase < phase_delta {
+        let x = phase / phase_delta;
+        -(1f32 - x) * (1f32 - x)
+    } else if phase > 1f32 - phase_delta {
+        let x = (phase - 1f32) / phase_delta;
+        (1f32 + x) * (1f32 + x)
+    } else {
+        0f32
+    }
+}
+
+/// Residual of a band-limited change in slope of one per sample at phase
+/// zero
+fn poly_blamp(phase: f32, phase_delta: f32) -> f32 {
+    let x = if phase < phase_delta {
+        phase / phase_delta
+    } else if phase > 1f32 - phase_delta {
+        (1f32 - phase) / phase_delta
+    } else {
+        return 0f32;
+    };
+    (1f32 - x).powi(3) / 6f32
+}
+
+fn wrap_phase(phase: f32) -> f32 {
+    if phase >= 1f32 {
+        phase - 1f32
+    } else {
+        phase
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::click::Channel;
+    use super::{Click, Oscillator, OscillatorQuality, Waveform};
+    use std::f64::consts;
+
+    const SAMPLE_RATE: f32 = 44_100f32;
+
+    // A whole number of cycles of a 3 kHz tone so that the harmonics fall
+    // exactly on every 300th bin and anything between them is energy aliased
+    // back from above the Nyquist frequency
+    const SAMPLES: usize = 4_410;
+    const FREQUENCY: f32 = 3_000f32;
+    const HARMONIC_BINS: usize = 300;
+
+    fn render(waveform: Waveform, quality: OscillatorQuality) -> Vec<f32> {
+        let click = Click {
+            channel: Channel::Both,
+            waveform,
+            frequency: FREQUENCY,
+            length: 1f64,
+            level: 1f32,
+        };
+        let mut oscillator = Oscillator::default();
+        oscillator.initialize(SAMPLE_RATE);
+        oscillator.update(0, 0);
+        let mut output = vec![0f32; SAMPLES];
+        oscillator.render(&click, quality, &mut output);
+        output
+    }
+
+    /// Fraction of the signal's energy that is not at a harmonic
+    fn aliased_energy(output: &[f32]) -> f64 {
+        let mut harmonic = 0f64;
+        let mut aliased = 0f64;
+        for bin in 1..=SAMPLES / 2 {
+            let (re, im) = output
+                .iter()
+                .enumerate()
+                .fold((0f64, 0f64), |(re, im), (i, &value)| {
+                    let angle = consts::TAU * (bin * i % SAMPLES) as f64 / SAMPLES as f64;
+                    (
+                        re + value as f64 * angle.cos(),
+                        im - value as f64 * angle.sin(),
+                    )
+                });
+            let energy = re * re + im * im;
+            if bin % HARMONIC_BINS == 0 {
+                harmonic += energy;
+            } else {
+                aliased += energy;
+            }
+        }
+        aliased / (harmonic + aliased)
+    }
+
+    #[test]
+    fn band_limited_waveforms_alias_less() {
+        for waveform in [Waveform::Square, Waveform::Saw, Waveform::Triangle] {
+            let draft = aliased_energy(&render(waveform, OscillatorQuality::Draft));
+            let band_limited = aliased_energy(&render(waveform, OscillatorQuality::BandLimited));
+            assert!(band_limited < 0.005f64, "{:?}", waveform);
+            assert!(band_limited * 20f64 < draft, "{:?}", waveform);
+        }
+    }
+
+    #[test]
+    fn tones_do_not_drift_over_hours() {
+        let click = Click {
+            channel: Channel::Both,
+            waveform: Waveform::Square,
+            frequency: 1_000f32,
+            length: 1f64,
+            level: 1f32,
+        };
+        let mut expected = vec![0f32; 1_024];
+        let mut oscillator = Oscillator::default();
+        oscillator.initialize(192_000f32);
+        oscillator.update(0, 0);
+        oscillator.render(&click, OscillatorQuality::BandLimited, &mut expected);
+
+        // A beat three hours later at 120 crotchets per minute
+        let mut output = vec![0f32; 1_024];
+        oscillator.update(5_400, 0);
+        oscillator.render(&click, OscillatorQuality::BandLimited, &mut output);
+        assert_eq!(expected, output);
+
+        // A tone sounding for three hours is back in phase since the hours
+        // are a whole number of cycles
+        oscillator.update(0, 0);
+        oscillator.samples = 3 * 60 * 60 * 192_000;
+        oscillator.render(&click, OscillatorQuality::BandLimited, &mut output);
+        for (expected, value) in expected.iter().zip(output) {
+            assert!((expected - value).abs() < 1e-3f32);
+        }
+    }
+
+    #[test]
+    fn sine_does_not_alias() {
+        let output = render(Waveform::Sine, OscillatorQuality::Draft);
+        assert!(aliased_energy(&output) < 1e-9f64);
+    }
+}
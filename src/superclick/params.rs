@@ -19,21 +19,269 @@
 // OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
+use super::click::Tier;
+use super::clock::ClockParams;
+use super::count::{ClickVoice, CountBank};
+use super::cue::{CueBank, CueTrack};
+use super::ducker::DuckingParams;
 use super::editor::create_default_editor_state;
+use super::gap::GapParams;
+use super::layer::LayerParams;
+use super::mix::MixMode;
+use super::oscillator::OscillatorQuality;
+use super::profile::{CustomProfile, DeviceProfile};
+use super::routing::{MirrorParams, RoutingParams};
+use super::setlist::{Setlist, Song};
+use super::theme::Theme;
+use super::timing::TimingParams;
 use nih_plug::prelude::*;
 use nih_plug_iced::IcedState;
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
 
 #[derive(Params)]
 pub struct SuperClickParams {
     #[persist = "editor-state"]
     pub editor_state: Arc<IcedState>,
+
+    #[persist = "custom-profile"]
+    pub custom_profile: RwLock<Option<CustomProfile>>,
+
+    #[persist = "setlist"]
+    pub setlist: RwLock<Setlist>,
+
+    #[persist = "setlist-position"]
+    pub setlist_position: AtomicUsize,
+
+    /// Setlist position whose preset was last applied: persisted so that
+    /// reopening a project keeps any changes made since
+    #[persist = "applied-setlist-position"]
+    pub applied_setlist_position: RwLock<Option<usize>>,
+
+    #[persist = "cue-track"]
+    pub cue_track: RwLock<CueTrack>,
+
+    /// Cue track prepared for playback: rebuilt whenever the cue track
+    /// changes
+    pub cue_bank: RwLock<CueBank>,
+
+    /// Directory containing a recorded count set
+    #[persist = "count-sample-dir"]
+    pub count_sample_dir: RwLock<Option<PathBuf>>,
+
+    /// Count set prepared for playback: rebuilt whenever the directory
+    /// changes
+    pub count_bank: RwLock<CountBank>,
+
+    /// Editor color theme: not a parameter so that it can't be automated
+    #[persist = "theme"]
+    pub theme: RwLock<Theme>,
+
+    #[nested(id_prefix = "clock", group = "Clock")]
+    pub clock: ClockParams,
+
+    #[id = "setlist"]
+    pub setlist_enabled: BoolParam,
+
+    #[id = "voice"]
+    pub voice: EnumParam<ClickVoice>,
+
+    #[id = "device-profile"]
+    pub device_profile: EnumParam<DeviceProfile>,
+
+    #[id = "mode"]
+    pub mode: EnumParam<MixMode>,
+
+    #[id = "quality"]
+    pub quality: EnumParam<OscillatorQuality>,
+
+    #[id = "click-gain"]
+    pub click_gain: FloatParam,
+
+    #[id = "dry-wet"]
+    pub dry_wet: FloatParam,
+
+    #[nested(id_prefix = "accent", group = "Accent")]
+    pub accent_routing: RoutingParams,
+
+    #[nested(id_prefix = "subaccent", group = "Subaccent")]
+    pub subaccent_routing: RoutingParams,
+
+    #[nested(id_prefix = "normal", group = "Normal")]
+    pub normal_routing: RoutingParams,
+
+    #[nested(id_prefix = "mirror", group = "Mirror")]
+    pub mirror: MirrorParams,
+
+    #[nested(id_prefix = "layer", group = "Layer")]
+    pub layer: LayerParams,
+
+    #[nested(id_prefix = "layer-routing", group = "Layer")]
+    pub layer_routing: RoutingParams,
+
+    #[nested(id_prefix = "ducking", group = "Ducking")]
+    pub ducking: DuckingParams,
+
+    #[nested(id_prefix = "timing", group = "Timing")]
+    pub timing: TimingParams,
+
+    #[nested(id_prefix = "gap", group = "Gap click")]
+    pub gap: GapParams,
+
+    #[id = "cues"]
+    pub cues_enabled: BoolParam,
+
+    #[nested(id_prefix = "cue", group = "Cues")]
+    pub cue_routing: RoutingParams,
+}
+
+impl SuperClickParams {
+    pub fn routing(&self, tier: Tier) -> &RoutingParams {
+        match tier {
+            Tier::Accent => &self.accent_routing,
+            Tier::Subaccent => &self.subaccent_routing,
+            Tier::Normal => &self.normal_routing,
+        }
+    }
+
+    /// Current setlist song if the setlist is enabled: `None` rather than
+    /// blocking while the editor replaces the setlist
+    pub fn current_song(&self) -> Option<Song> {
+        if !self.setlist_enabled.value() {
+            return None;
+        }
+
+        self.setlist
+            .try_read()
+            .ok()
+            .and_then(|setlist| setlist.song(&self.setlist_position))
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.theme.read().map(|theme| *theme).unwrap_or_default()
+    }
+
+    /// Rebuilds the cue bank from the cue track loading any recordings
+    pub fn reload_cue_bank(&self) {
+        let bank = match self.cue_track.read() {
+            Ok(cue_track) => CueBank::load(&cue_track),
+            Err(_) => return,
+        };
+        if let Ok(mut guard) = self.cue_bank.write() {
+            *guard = bank;
+        }
+    }
+
+    /// Rebuilds the count bank from the count set directory
+    pub fn reload_count_bank(&self) {
+        let bank = match self.count_sample_dir.read() {
+            Ok(count_sample_dir) => CountBank::load(count_sample_dir.as_deref()),
+            Err(_) => return,
+        };
+        if let Ok(mut guard) = self.count_bank.write() {
+            *guard = bank;
+        }
+    }
+
+    /// Setlist position whose preset has yet to be applied if any
+    pub fn pending_song_preset(&self) -> Option<usize> {
+        if !self.setlist_enabled.value() {
+            return None;
+        }
+
+        let position = self.setlist_position.load(Ordering::Relaxed);
+        match self.applied_setlist_position.try_read() {
+            Ok(applied) if *applied != Some(position) => Some(position),
+            _ => None,
+        }
+    }
+
+    /// Name of the preset of the song at the given setlist position if it
+    /// has one, recording that it has been applied
+    pub fn take_song_preset(&self, position: usize) -> Option<String> {
+        if let Ok(mut applied) = self.applied_setlist_position.write() {
+            *applied = Some(position);
+        }
+
+        self.setlist
+            .read()
+            .ok()
+            .and_then(|setlist| setlist.songs.get(position)?.preset.clone())
+    }
+
+    /// Makes the current song's preset apply again
+    pub fn forget_applied_song_preset(&self) {
+        if let Ok(mut applied) = self.applied_setlist_position.write() {
+            *applied = None;
+        }
+    }
+
+    pub fn step_setlist(&self, forward: bool) {
+        if let Ok(setlist) = self.setlist.try_read() {
+            setlist.step(&self.setlist_position, forward);
+        }
+    }
 }
 
 impl Default for SuperClickParams {
     fn default() -> Self {
         Self {
             editor_state: create_default_editor_state(),
+            custom_profile: RwLock::new(None),
+            setlist: RwLock::new(Setlist::default()),
+            setlist_position: AtomicUsize::new(0),
+            applied_setlist_position: RwLock::new(None),
+            cue_track: RwLock::new(CueTrack::default()),
+            cue_bank: RwLock::new(CueBank::default()),
+            count_sample_dir: RwLock::new(None),
+            count_bank: RwLock::new(CountBank::default()),
+            theme: RwLock::new(Theme::default()),
+            clock: ClockParams::default(),
+            setlist_enabled: BoolParam::new("Setlist", false),
+            voice: EnumParam::new("Voice", ClickVoice::Tone),
+            device_profile: EnumParam::new("Device profile", DeviceProfile::default()),
+            mode: EnumParam::new("Mode", MixMode::Replace),
+            quality: EnumParam::new("Quality", OscillatorQuality::BandLimited),
+            click_gain: new_gain_param("Click gain"),
+            dry_wet: FloatParam::new(
+                "Dry/wet",
+                0.5f32,
+                FloatRange::Linear {
+                    min: 0f32,
+                    max: 1f32,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            accent_routing: RoutingParams::default(),
+            subaccent_routing: RoutingParams::default(),
+            normal_routing: RoutingParams::default(),
+            mirror: MirrorParams::default(),
+            layer: LayerParams::default(),
+            layer_routing: RoutingParams::default(),
+            ducking: DuckingParams::default(),
+            timing: TimingParams::default(),
+            gap: GapParams::default(),
+            cues_enabled: BoolParam::new("Cues", false),
+            cue_routing: RoutingParams::default(),
         }
     }
 }
+
+/// Gain parameter in decibels from -60 dB to +6 dB defaulting to unity
+pub fn new_gain_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        util::db_to_gain(0f32),
+        FloatRange::Skewed {
+            min: util::db_to_gain(-60f32),
+            max: util::db_to_gain(6f32),
+            factor: FloatRange::gain_skew_factor(-60f32, 6f32),
+        },
+    )
+    .with_unit(" dB")
+    .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
+    .with_string_to_value(formatters::s2v_f32_gain_to_db())
+}
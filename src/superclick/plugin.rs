@@ -19,41 +19,185 @@
 // OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
-use super::click::{Channel, Click};
+use super::click::{Channel, Click, Tier};
+use super::clock::{ClockSettings, ClockSource, InternalClock, TempoUnit};
+use super::count::{ClickVoice, CountBank, CountPlayer};
+use super::cue::CuePlayer;
 use super::display::{Display, Playhead};
+use super::ducker::Ducker;
 use super::editor::create_editor;
+use super::layer::LayerBeat;
+use super::mix::ReplacedInput;
+use super::oscillator::Oscillator;
 use super::params::SuperClickParams;
+use super::profile::{BuiltInProfiles, Profile};
+use super::routing::{ChannelGains, AUX_BUS_COUNT};
+use super::setlist::{Song, NEXT_SONG_NOTE, PREVIOUS_SONG_NOTE};
+use super::timing::DryDelay;
 use crate::error::Error;
-use crate::music_theory::TimeSignatureTop;
+use crate::music_theory::{Meter, MusicalPosition};
 use crate::package::{PACKAGE_AUTHORS, PACKAGE_HOME_PAGE, PACKAGE_VERSION};
 use crate::result::{GetOr, Result};
 use nih_plug::prelude::*;
-use std::f32::consts;
+use std::ops::Range;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-const LEFT_CHANNEL_ID: usize = 0;
-const RIGHT_CHANNEL_ID: usize = 1;
+const AUX_INPUT_NAMES: &[&str] = &["Sidechain"];
+const AUX_OUTPUT_NAMES: &[&str; AUX_BUS_COUNT] = &["Aux 1", "Aux 2", "Aux 3"];
+
+/// Largest change in the host's tempo per sample treated as a ramp rather
+/// than a jump
+const MAX_HOST_TEMPO_SLOPE: f64 = 0.001f64;
 
 pub struct SuperClick {
     params: Arc<SuperClickParams>,
     display: Arc<Display>,
     sample_rate: f32,
-    phase: f32,
+    oscillator: Oscillator,
+    mirror_oscillators: [Oscillator; AUX_BUS_COUNT],
+    layer_oscillator: Oscillator,
+    ducker: Ducker,
+    profiles: BuiltInProfiles,
+    clock: InternalClock,
+    setlist_position: usize,
+    cue_player: CuePlayer,
+    count_player: CountPlayer,
+
+    /// Host tempo and length of the previous block
+    last_host_tempo: Option<(f64, usize)>,
+
+    dry_delay: DryDelay,
+    latency_samples: u32,
+
+    /// Block of voice output mixed into the routed channels, allocated for
+    /// the largest buffer
+    scratch: Vec<f32>,
+
+    replaced_input: ReplacedInput,
 }
 
 impl SuperClick {
+    #[cfg(feature = "bench")]
+    pub(crate) fn with_params(params: SuperClickParams) -> Self {
+        Self {
+            params: Arc::new(params),
+            ..Self::default()
+        }
+    }
+
+    /// Allocates everything that processing needs so that processing never
+    /// allocates
+    pub(crate) fn prepare(
+        &mut self,
+        sample_rate: f32,
+        channel_count: usize,
+        max_buffer_size: usize,
+    ) {
+        self.sample_rate = sample_rate;
+        self.scratch = vec![0f32; max_buffer_size];
+        self.replaced_input.initialize(max_buffer_size);
+        self.oscillator.initialize(sample_rate);
+        for oscillator in self.mirror_oscillators.iter_mut() {
+            oscillator.initialize(sample_rate);
+        }
+        self.layer_oscillator.initialize(sample_rate);
+        self.dry_delay.initialize(channel_count, sample_rate);
+        self.ducker.initialize(max_buffer_size);
+        self.latency_samples = self.params.timing.latency_samples(sample_rate);
+    }
+
+    /// Processes a block playing from the internal clock whatever the clock
+    /// parameters are since only NIH-plug can create a host transport
+    #[cfg(feature = "bench")]
+    pub(crate) fn process_internal_clock(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+    ) {
+        let playhead = ClockSettings::new(&self.params.clock, None)
+            .ok()
+            .and_then(|settings| {
+                self.clock
+                    .tick(true, &settings, buffer.samples(), self.sample_rate)
+            });
+        self.render(buffer, aux, playhead.as_ref());
+    }
+
     fn process_inner(
         &mut self,
         buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
         context: &mut impl ProcessContext<Self>,
     ) -> Result<Option<Playhead>> {
-        let playhead = self.get_playhead(context.transport())?;
+        // The host delivers the transport early by the reported latency
+        let latency_samples = self.params.timing.latency_samples(self.sample_rate);
+        if latency_samples != self.latency_samples {
+            self.latency_samples = latency_samples;
+            context.set_latency_samples(latency_samples);
+        }
 
-        if let Some(ref playhead) = playhead {
-            self.write_samples(playhead, buffer);
+        self.handle_events(context);
+
+        let playhead =
+            self.get_playhead(context.transport(), context.plugin_api(), buffer.samples());
+        self.render(buffer, aux, playhead.as_ref().ok().and_then(Option::as_ref));
+        playhead
+    }
+
+    fn render(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        playhead: Option<&Playhead>,
+    ) {
+        // Auxiliary outputs carry clicks only
+        for output in aux.outputs.iter_mut() {
+            for channel in output.as_slice().iter_mut() {
+                channel.fill(0f32);
+            }
+        }
+
+        let dry_gain = self
+            .params
+            .mode
+            .value()
+            .dry_gain(self.params.dry_wet.value());
+        if dry_gain != 1f32 {
+            for channel in buffer.as_slice().iter_mut() {
+                for sample in channel.iter_mut() {
+                    *sample *= dry_gain;
+                }
+            }
         }
 
-        Ok(playhead)
+        // The dry signal is delayed by the latency to stay aligned with the
+        // rest of the host's mix
+        self.dry_delay.process(buffer, self.latency_samples);
+        self.replaced_input.reset(buffer.samples());
+
+        self.ducker
+            .process(&self.params.ducking, aux.inputs.first(), self.sample_rate);
+
+        if let Some(playhead) = playhead {
+            // Output is heard the latency after the transport position and
+            // clicks are shifted by the timing offset on top of that
+            let shift_samples = -(self.latency_samples as i64
+                + self.params.timing.offset_samples(self.sample_rate));
+            let scheduled = playhead.advance(shift_samples, self.sample_rate);
+            let scheduled = Playhead {
+                meter: self.params.clock.click_on.value().apply(scheduled.meter),
+                ..scheduled
+            };
+
+            self.write_layer(&scheduled, buffer, aux);
+            self.write_mirrors(&scheduled, buffer, aux);
+            self.write_samples(&scheduled, buffer, aux);
+            self.write_cue(&scheduled, buffer, aux);
+        } else {
+            self.cue_player.reset();
+            self.count_player.reset();
+        }
     }
 
     fn update_display(&self, result: Result<Option<Playhead>>) {
@@ -65,91 +209,559 @@ impl SuperClick {
         }
     }
 
-    fn get_playhead(&self, transport: &Transport) -> Result<Option<Playhead>> {
+    fn handle_events(&mut self, context: &mut impl ProcessContext<Self>) {
+        while let Some(event) = context.next_event() {
+            if let NoteEvent::NoteOn { note, .. } = event {
+                match note {
+                    PREVIOUS_SONG_NOTE => self.params.step_setlist(false),
+                    NEXT_SONG_NOTE => self.params.step_setlist(true),
+                    _ => {}
+                }
+            }
+        }
+
+        // Changing song restarts the internal clock including any count-in
+        let setlist_position = self.params.setlist_position.load(Ordering::Relaxed);
+        if setlist_position != self.setlist_position {
+            self.setlist_position = setlist_position;
+            self.clock.reset();
+        }
+    }
+
+    fn get_playhead(
+        &mut self,
+        transport: &Transport,
+        plugin_api: PluginApi,
+        samples: usize,
+    ) -> Result<Option<Playhead>> {
+        let song = self.params.current_song();
+        match self.params.clock.clock_source(plugin_api) {
+            ClockSource::Host => {
+                self.clock.reset();
+                let playhead =
+                    Self::get_host_playhead(transport, song, self.params.clock.beat_unit.value())?;
+                Ok(match playhead {
+                    Some(playhead) => Some(Playhead {
+                        tempo_slope: self.host_tempo_slope(playhead.tempo, samples),
+                        ..playhead
+                    }),
+                    None => {
+                        self.last_host_tempo = None;
+                        None
+                    }
+                })
+            }
+            ClockSource::Internal => {
+                self.last_host_tempo = None;
+                let settings = ClockSettings::new(&self.params.clock, song)?;
+                Ok(self.clock.tick(
+                    self.params.clock.run.value(),
+                    &settings,
+                    samples,
+                    self.sample_rate,
+                ))
+            }
+        }
+    }
+
+    /// Change in the host's tempo per sample
+    ///
+    /// Hosts only report the tempo at the start of each block so ramps are
+    /// extrapolated from the change since the previous block while larger
+    /// changes are treated as jumps
+    fn host_tempo_slope(&mut self, tempo: f64, samples: usize) -> f64 {
+        let slope = match self.last_host_tempo {
+            Some((last_tempo, last_samples)) if last_samples > 0 => {
+                let slope = (tempo - last_tempo) / last_samples as f64;
+                if slope.abs() <= MAX_HOST_TEMPO_SLOPE {
+                    slope
+                } else {
+                    0f64
+                }
+            }
+            _ => 0f64,
+        };
+        self.last_host_tempo = Some((tempo, samples));
+        slope
+    }
+
+    fn get_host_playhead(
+        transport: &Transport,
+        song: Option<Song>,
+        tempo_unit: TempoUnit,
+    ) -> Result<Option<Playhead>> {
         Ok(if transport.playing {
             let tempo = transport.tempo.get_or(Error::TempoUnavailable)?;
             let bar_number = transport.bar_number().get_or(Error::BarNumberUnavailable)?;
-            let bar_start_pos_crotchets = transport
-                .bar_start_pos_beats()
-                .get_or(Error::BarStartPosBeatsUnavailable)?;
-            let pos_crotchets = transport.pos_beats().get_or(Error::PosBeatsUnavailable)?;
+            let bar_start = MusicalPosition::from_crotchets(
+                transport
+                    .bar_start_pos_beats()
+                    .get_or(Error::BarStartPosBeatsUnavailable)?,
+            );
+            let pos = MusicalPosition::from_crotchets(
+                transport.pos_beats().get_or(Error::PosBeatsUnavailable)?,
+            );
             let time_sig_numerator = transport
                 .time_sig_numerator
                 .get_or(Error::TimeSigNumeratorUnavailable)?;
             let time_sig_denominator = transport
                 .time_sig_denominator
                 .get_or(Error::TimeSignDenominatorUnavailable)?;
+            let meter = Meter::new(
+                time_sig_numerator.try_into()?,
+                time_sig_denominator.try_into()?,
+            );
+            // Tempo and meter come from the host so only the song's accents
+            // apply and only if they fit the host's meter
+            let meter = song
+                .and_then(|song| song.accent_pattern)
+                .and_then(|grouping| meter.with_grouping(grouping).ok())
+                .unwrap_or(meter);
             Some(Playhead {
                 tempo,
+                tempo_slope: 0f64,
                 bar_number,
-                bar_start_pos_crotchets,
-                pos_crotchets,
-                time_signature_top: time_sig_numerator.try_into()?,
-                time_signature_bottom: time_sig_denominator.try_into()?,
+                bar_start,
+                pos,
+                meter: tempo_unit.apply(meter),
             })
         } else {
             None
         })
     }
 
-    fn calculate_sine(&mut self, frequency: f32) -> f32 {
-        let phase_delta = frequency / self.sample_rate;
-        let sine = (self.phase * consts::TAU).sin();
+    fn write_samples(
+        &mut self,
+        playhead: &Playhead,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+    ) {
+        let profile = self.profiles.select(
+            self.params.device_profile.value(),
+            &self.params.custom_profile,
+        );
+        let voice = self.params.voice.value();
+        if voice != ClickVoice::Count {
+            self.count_player.reset();
+        }
+
+        self.write_pass(playhead, &profile, voice, Destination::Routed, buffer, aux);
+    }
+
+    /// Writes every tier to each auxiliary bus that mirrors a device
+    /// profile: mirrors always play tones since a haptic device can't
+    /// speak a count
+    fn write_mirrors(
+        &mut self,
+        playhead: &Playhead,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+    ) {
+        for (index, mirror) in self.params.mirror.mirrors().into_iter().enumerate() {
+            let device_profile = match mirror.device_profile() {
+                Some(device_profile) if index < aux.outputs.len() => device_profile,
+                _ => {
+                    self.mirror_oscillators[index].reset();
+                    continue;
+                }
+            };
+
+            let profile = self
+                .profiles
+                .select(device_profile, &self.params.custom_profile);
+            self.write_pass(
+                playhead,
+                &profile,
+                ClickVoice::Tone,
+                Destination::Mirror(index),
+                buffer,
+                aux,
+            );
+        }
+    }
+
+    /// Writes each run of samples belonging to a sounding beat so that beats
+    /// start on the exact sample even when they fall within the block
+    fn write_pass(
+        &mut self,
+        playhead: &Playhead,
+        profile: &Profile,
+        voice: ClickVoice,
+        destination: Destination,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+    ) {
+        // The editor only holds the lock while replacing the bank
+        let params = self.params.clone();
+        let count_bank = match voice {
+            ClickVoice::Tone => None,
+            ClickVoice::Count => params.count_bank.try_read().ok(),
+        };
+        let count_bank = count_bank.as_deref();
+
+        let samples = buffer.samples();
+        let mut start = 0;
+        while start < samples {
+            let (state, end) = self.next_run(playhead, start..samples, |playhead| {
+                self.beat_span(playhead, profile, count_bank)
+            });
+            let range = start..end;
+            start = end;
+
+            let beat = match state {
+                Some(beat) => beat,
+                None => continue,
+            };
+
+            let sound = match (beat.voice, count_bank) {
+                (ClickVoice::Count, Some(bank)) => Sound::Count(bank),
+                _ => Sound::Tone(*profile.click(beat.tier)),
+            };
+
+            // Clicks routed to an auxiliary bus that the host hasn't
+            // connected go to the main output instead
+            let bus = match destination {
+                Destination::Routed => self.params.routing(beat.tier).bus.value().aux_index(),
+                Destination::Mirror(index) => Some(index),
+            };
+            match bus {
+                Some(index) if index < aux.outputs.len() => self.write_beat(
+                    destination,
+                    beat,
+                    sound,
+                    range,
+                    &mut aux.outputs[index],
+                    false,
+                ),
+                _ => self.write_beat(destination, beat, sound, range, buffer, true),
+            }
+        }
+    }
+
+    /// State at the first of the given samples and the sample that the run
+    /// in that state ends on given the state at the playhead and the position
+    /// until which that holds
+    fn next_run<S>(
+        &self,
+        playhead: &Playhead,
+        samples: Range<usize>,
+        span: impl Fn(&Playhead) -> (Option<S>, MusicalPosition),
+    ) -> (Option<S>, usize) {
+        let (state, until) = span(&playhead.advance(samples.start as i64, self.sample_rate));
+        let end = playhead.first_sample_at(until, samples.start + 1..samples.end, self.sample_rate);
+        (state, end)
+    }
 
-        self.phase += phase_delta;
-        if self.phase >= 1.0 {
-            self.phase -= 1.0;
+    /// Beat sounding at the playhead if any and the position until which
+    /// that holds: beats are counted if there is a count bank with a
+    /// recording for the beat and otherwise play tones
+    fn beat_span(
+        &self,
+        playhead: &Playhead,
+        profile: &Profile,
+        count_bank: Option<&CountBank>,
+    ) -> (Option<BeatState>, MusicalPosition) {
+        let meter = &playhead.meter;
+        let position = playhead.bar_beat_tick();
+        let beat_start = playhead.pos - position.since_beat();
+        let next_beat = (playhead.bar_start + meter.beat_start(position.beat + 1))
+            .min(playhead.bar_start + meter.bar_length());
+
+        // Silent bars still report the playhead so that the display keeps
+        // running
+        if self.params.gap.is_muted(position.bar)
+            || !(0..meter.beat_count()).contains(&position.beat)
+        {
+            return (None, next_beat);
+        }
+
+        let tier = Tier::for_beat(meter, position.beat);
+        let (voice, length) = match count_bank.and_then(|bank| bank.duration(position.beat)) {
+            Some(seconds) => (
+                ClickVoice::Count,
+                MusicalPosition::from_samples(
+                    (seconds * self.sample_rate as f64).ceil() as u64,
+                    playhead.tempo,
+                    self.sample_rate,
+                ),
+            ),
+            None => (
+                ClickVoice::Tone,
+                MusicalPosition::from_crotchets(profile.click(tier).length),
+            ),
+        };
+        let end = beat_start + length;
+        if playhead.pos > end {
+            return (None, next_beat);
         }
 
-        sine
+        let beat = BeatState {
+            bar_number: position.bar,
+            index: position.beat,
+            tier,
+            voice,
+        };
+        (
+            Some(beat),
+            (end + MusicalPosition::from_ticks(1)).min(next_beat),
+        )
     }
 
-    fn write_samples(&mut self, playhead: &Playhead, buffer: &mut Buffer) {
-        fn get_click(time_signature_top: TimeSignatureTop, index: i32) -> Click {
-            if index == 0 {
-                Click::ACCENT
-            } else if time_signature_top.is_accented(index) {
-                Click::SUBACCENT
-            } else {
-                Click::NORMAL
+    fn write_beat(
+        &mut self,
+        destination: Destination,
+        beat: BeatState,
+        sound: Sound,
+        range: Range<usize>,
+        buffer: &mut Buffer,
+        is_main: bool,
+    ) {
+        match sound {
+            Sound::Tone(click) => {
+                self.write_click(destination, beat, &click, range, buffer, is_main)
             }
+            Sound::Count(bank) => self.write_count(beat, bank, range, buffer, is_main),
         }
+    }
 
-        let pos_in_bar_crotchets = playhead.pos_crotchets - playhead.bar_start_pos_crotchets;
-        for i in 0..playhead.time_signature_top.as_number() {
-            let click = get_click(playhead.time_signature_top, i);
-            let temp = i as f64 * 4f64 / playhead.time_signature_bottom.as_number() as f64;
-            if pos_in_bar_crotchets >= temp && pos_in_bar_crotchets <= temp + click.length {
-                for channel_samples in buffer.iter_samples() {
-                    let value = self.calculate_sine(click.frequency);
-
-                    // There's probably a more efficient way to do this...
-                    for (channel_id, sample) in channel_samples.into_iter().enumerate() {
-                        let is_audible = match click.channel {
-                            Channel::Left => channel_id == LEFT_CHANNEL_ID,
-                            Channel::Right => channel_id == RIGHT_CHANNEL_ID,
-                            Channel::Both => {
-                                channel_id == LEFT_CHANNEL_ID || channel_id == RIGHT_CHANNEL_ID
-                            }
-                        };
-                        if is_audible {
-                            *sample = value;
-                        }
-                    }
+    fn write_layer(
+        &mut self,
+        playhead: &Playhead,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+    ) {
+        if !self.params.layer.enabled.value() {
+            return;
+        }
+
+        let samples = buffer.samples();
+        let mut start = 0;
+        while start < samples {
+            let (state, end) = self.next_run(playhead, start..samples, |playhead| {
+                self.layer_span(playhead)
+            });
+            let range = start..end;
+            start = end;
+
+            let beat = match state {
+                Some(beat) => beat,
+                None => continue,
+            };
+
+            match self.params.layer_routing.bus.value().aux_index() {
+                Some(index) if index < aux.outputs.len() => {
+                    self.write_layer_click(beat, range, &mut aux.outputs[index], false)
                 }
-                break;
+                _ => self.write_layer_click(beat, range, buffer, true),
+            }
+        }
+    }
+
+    /// Layer beat sounding at the playhead if any and the position until
+    /// which that holds
+    fn layer_span(&self, playhead: &Playhead) -> (Option<BeatState>, MusicalPosition) {
+        let bar_end = playhead.bar_start + playhead.meter.bar_length();
+        if self.params.gap.is_muted(playhead.bar_number) {
+            return (None, bar_end);
+        }
+
+        let layer = &self.params.layer;
+        let next_beat = layer
+            .next_beat(playhead.pos, playhead.bar_start, &playhead.meter)
+            .min(bar_end);
+        match layer
+            .beat_at(playhead.pos, playhead.bar_start, &playhead.meter)
+            .filter(LayerBeat::is_sounding)
+        {
+            Some(beat) => (
+                Some(BeatState {
+                    bar_number: playhead.bar_number,
+                    index: beat.index,
+                    tier: beat.tier(),
+                    voice: ClickVoice::Tone,
+                }),
+                (playhead.pos + beat.remaining()).min(next_beat),
+            ),
+            None => (None, next_beat),
+        }
+    }
+
+    fn write_layer_click(
+        &mut self,
+        beat: BeatState,
+        range: Range<usize>,
+        buffer: &mut Buffer,
+        is_main: bool,
+    ) {
+        self.layer_oscillator.update(beat.bar_number, beat.index);
+
+        let click = self.params.layer.click(beat.tier);
+        let gains = self
+            .params
+            .layer_routing
+            .channel_gains(buffer.channels(), click.channel)
+            .scaled(click.level * self.click_gain());
+        let scratch = &mut self.scratch[..range.len()];
+        self.layer_oscillator
+            .render(&click, self.params.quality.value(), scratch);
+        self.mix_scratch(&gains, range, buffer, is_main);
+    }
+
+    /// Mixes any cue announcement under the clicks
+    fn write_cue(&mut self, playhead: &Playhead, buffer: &mut Buffer, aux: &mut AuxiliaryBuffers) {
+        if !self.params.cues_enabled.value() {
+            self.cue_player.reset();
+            return;
+        }
+
+        // The editor only holds the lock while replacing the bank
+        let bank = match self.params.cue_bank.try_read() {
+            Ok(bank) => bank,
+            Err(_) => return,
+        };
+
+        self.cue_player.update(&bank, playhead.bar_number);
+        if !self.cue_player.is_announcing() {
+            return;
+        }
+
+        let scratch = &mut self.scratch[..buffer.samples()];
+        self.cue_player.render(&bank, scratch, self.sample_rate);
+
+        let routing = &self.params.cue_routing;
+        match routing.bus.value().aux_index() {
+            Some(index) if index < aux.outputs.len() => {
+                let output = &mut aux.outputs[index];
+                let gains = routing.channel_gains(output.channels(), Channel::Both);
+                gains.mix(output.as_slice(), 0, scratch);
             }
+            _ => {
+                let gains = routing.channel_gains(buffer.channels(), Channel::Both);
+                gains.mix(buffer.as_slice(), 0, scratch);
+            }
+        }
+    }
+
+    /// Gain applied to every click voice on top of any ducking
+    fn click_gain(&self) -> f32 {
+        self.params.click_gain.value()
+            * self
+                .params
+                .mode
+                .value()
+                .wet_gain(self.params.dry_wet.value())
+    }
+
+    fn write_count(
+        &mut self,
+        beat: BeatState,
+        bank: &CountBank,
+        range: Range<usize>,
+        buffer: &mut Buffer,
+        is_main: bool,
+    ) {
+        self.count_player.update(beat.bar_number, beat.index);
+
+        let gains = self
+            .params
+            .routing(beat.tier)
+            .channel_gains(buffer.channels(), Channel::Both)
+            .scaled(self.click_gain());
+        let scratch = &mut self.scratch[..range.len()];
+        self.count_player.render(bank, scratch, self.sample_rate);
+        self.mix_scratch(&gains, range, buffer, is_main);
+    }
+
+    fn write_click(
+        &mut self,
+        destination: Destination,
+        beat: BeatState,
+        click: &Click,
+        range: Range<usize>,
+        buffer: &mut Buffer,
+        is_main: bool,
+    ) {
+        let gains = self
+            .params
+            .routing(beat.tier)
+            .channel_gains(buffer.channels(), click.channel)
+            .scaled(click.level * self.click_gain());
+        let oscillator = match destination {
+            Destination::Routed => &mut self.oscillator,
+            Destination::Mirror(index) => &mut self.mirror_oscillators[index],
+        };
+        oscillator.update(beat.bar_number, beat.index);
+        let scratch = &mut self.scratch[..range.len()];
+        oscillator.render(click, self.params.quality.value(), scratch);
+        self.mix_scratch(&gains, range, buffer, is_main);
+    }
+
+    /// Ducks the voice in the scratch buffer and adds it to the routed
+    /// channels, first replacing the input on the main output in Replace
+    /// mode
+    fn mix_scratch(
+        &mut self,
+        gains: &ChannelGains,
+        range: Range<usize>,
+        buffer: &mut Buffer,
+        is_main: bool,
+    ) {
+        let scratch = &mut self.scratch[..range.len()];
+        self.ducker.apply(range.start, scratch);
+        if is_main && self.params.mode.value().replaces_input() {
+            self.replaced_input
+                .replace(buffer.as_slice(), gains, range.clone());
         }
+        gains.mix(buffer.as_slice(), range.start, scratch);
     }
 }
 
+/// Buses that a pass over the block writes clicks to
+#[derive(Clone, Copy)]
+enum Destination {
+    /// Each tier's routed bus
+    Routed,
+
+    /// Every tier to the auxiliary bus with the given index
+    Mirror(usize),
+}
+
+/// What a beat plays
+#[derive(Clone, Copy)]
+enum Sound<'a> {
+    Tone(Click),
+    Count(&'a CountBank),
+}
+
+/// Beat sounding at a sample
+#[derive(Clone, Copy, PartialEq)]
+struct BeatState {
+    bar_number: i32,
+    index: i32,
+    tier: Tier,
+    voice: ClickVoice,
+}
+
 impl Default for SuperClick {
     fn default() -> Self {
         Self {
             params: Arc::new(SuperClickParams::default()),
             display: Arc::new(Display::default()),
             sample_rate: 0f32,
-            phase: 0f32,
+            oscillator: Oscillator::default(),
+            mirror_oscillators: Default::default(),
+            layer_oscillator: Oscillator::default(),
+            ducker: Ducker::default(),
+            profiles: BuiltInProfiles::default(),
+            clock: InternalClock::default(),
+            setlist_position: 0,
+            cue_player: CuePlayer::default(),
+            count_player: CountPlayer::default(),
+            last_host_tempo: None,
+            dry_delay: DryDelay::default(),
+            latency_samples: 0,
+            scratch: Vec::new(),
+            replaced_input: ReplacedInput::default(),
         }
     }
 }
@@ -165,15 +777,42 @@ impl Plugin for SuperClick {
         AudioIOLayout {
             main_input_channels: NonZeroU32::new(2),
             main_output_channels: NonZeroU32::new(2),
+            aux_input_ports: &[new_nonzero_u32(2)],
+            aux_output_ports: &[new_nonzero_u32(2); AUX_BUS_COUNT],
+            names: PortNames {
+                aux_inputs: AUX_INPUT_NAMES,
+                aux_outputs: AUX_OUTPUT_NAMES,
+                ..PortNames::const_default()
+            },
+            ..AudioIOLayout::const_default()
+        },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(4),
+            main_output_channels: NonZeroU32::new(4),
+            aux_input_ports: &[new_nonzero_u32(4)],
+            aux_output_ports: &[new_nonzero_u32(4); AUX_BUS_COUNT],
+            names: PortNames {
+                aux_inputs: AUX_INPUT_NAMES,
+                aux_outputs: AUX_OUTPUT_NAMES,
+                ..PortNames::const_default()
+            },
             ..AudioIOLayout::const_default()
         },
         AudioIOLayout {
             main_input_channels: NonZeroU32::new(1),
             main_output_channels: NonZeroU32::new(1),
+            aux_input_ports: &[new_nonzero_u32(1)],
+            aux_output_ports: &[new_nonzero_u32(1); AUX_BUS_COUNT],
+            names: PortNames {
+                aux_inputs: AUX_INPUT_NAMES,
+                aux_outputs: AUX_OUTPUT_NAMES,
+                ..PortNames::const_default()
+            },
             ..AudioIOLayout::const_default()
         },
     ];
 
+    const MIDI_INPUT: MidiConfig = MidiConfig::Basic;
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
     type SysExMessage = ();
@@ -193,22 +832,45 @@ impl Plugin for SuperClick {
 
     fn initialize(
         &mut self,
-        _audio_io_layout: &AudioIOLayout,
+        audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
-        _context: &mut impl InitContext<Self>,
+        context: &mut impl InitContext<Self>,
     ) -> bool {
-        self.sample_rate = buffer_config.sample_rate;
+        self.prepare(
+            buffer_config.sample_rate,
+            audio_io_layout
+                .main_output_channels
+                .map_or(0, |channels| channels.get() as usize),
+            buffer_config.max_buffer_size as usize,
+        );
+        context.set_latency_samples(self.latency_samples);
+        self.params.reload_cue_bank();
+        self.params.reload_count_bank();
         self.display.update(None, &None);
         true
     }
 
+    fn reset(&mut self) {
+        self.oscillator.reset();
+        for oscillator in self.mirror_oscillators.iter_mut() {
+            oscillator.reset();
+        }
+        self.layer_oscillator.reset();
+        self.ducker.reset();
+        self.clock.reset();
+        self.cue_player.reset();
+        self.count_player.reset();
+        self.last_host_tempo = None;
+        self.dry_delay.reset();
+    }
+
     fn process(
         &mut self,
         buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
+        aux: &mut AuxiliaryBuffers,
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        let result = self.process_inner(buffer, context);
+        let result = self.process_inner(buffer, aux, context);
         self.update_display(result);
         ProcessStatus::Normal
     }
@@ -223,6 +885,17 @@ impl ClapPlugin for SuperClick {
         ClapFeature::AudioEffect,
         ClapFeature::Stereo,
         ClapFeature::Mono,
+        ClapFeature::Surround,
         ClapFeature::Utility,
     ];
 }
+
+#[cfg(feature = "vst3")]
+impl Vst3Plugin for SuperClick {
+    const VST3_CLASS_ID: [u8; 16] = *b"RcookSuperClick1";
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] = &[
+        Vst3SubCategory::Fx,
+        Vst3SubCategory::Tools,
+        Vst3SubCategory::Generator,
+    ];
+}
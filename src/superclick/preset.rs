@@ -0,0 +1,229 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::params::SuperClickParams;
+use super::profile::CustomProfile;
+use crate::error::Error;
+use crate::package::PACKAGE_NAME;
+use crate::result::{GetOr, Result};
+use nih_plug::nih_error;
+use nih_plug::prelude::{GuiContext, Params};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env::var_os;
+use std::fs::{create_dir_all, read_dir, read_to_string, rename, write};
+use std::path::{Path, PathBuf};
+
+const PRESET_EXTENSION: &str = "json";
+
+/// Parameters that control the transport or the setlist rather than the
+/// click and are therefore not part of a preset: applying a song's preset
+/// must not stop the clock or leave the setlist
+const EXCLUDED_PARAM_IDS: &[&str] = &["clock_source", "clock_run", "setlist"];
+
+/// Named snapshot of the complete click configuration
+#[derive(Deserialize, Serialize)]
+pub struct Preset {
+    pub name: String,
+
+    /// Normalized parameter values keyed by parameter ID
+    params: BTreeMap<String, f32>,
+
+    #[serde(default)]
+    custom_profile: Option<CustomProfile>,
+}
+
+impl Preset {
+    pub fn capture(name: &str, params: &SuperClickParams) -> Result<Self> {
+        validate_name(name)?;
+
+        let values = params
+            .param_map()
+            .into_iter()
+            .filter(|(id, _, _)| !EXCLUDED_PARAM_IDS.contains(&id.as_str()))
+            // SAFETY: the pointers come from the plugin's own parameters
+            // which live as long as `params`
+            .map(|(id, ptr, _)| (id, unsafe { ptr.unmodulated_normalized_value() }))
+            .collect();
+
+        let custom_profile = params
+            .custom_profile
+            .read()
+            .ok()
+            .and_then(|custom_profile| custom_profile.clone());
+
+        Ok(Self {
+            name: String::from(name),
+            params: values,
+            custom_profile,
+        })
+    }
+
+    /// Sets the plugin's parameters from the preset: parameters that don't
+    /// appear in the preset, such as ones added in later versions, are left
+    /// unchanged
+    pub fn apply(&self, params: &SuperClickParams, context: &dyn GuiContext) {
+        if let Some(ref custom_profile) = self.custom_profile {
+            if let Ok(mut guard) = params.custom_profile.write() {
+                *guard = Some(custom_profile.clone());
+            }
+        }
+
+        for (id, ptr, _) in params.param_map() {
+            if let Some(value) = self.params.get(&id) {
+                // SAFETY: the pointers come from the plugin's own
+                // parameters which live as long as `params`
+                unsafe {
+                    context.raw_begin_set_parameter(ptr);
+                    context.raw_set_parameter_normalized(ptr, *value);
+                    context.raw_end_set_parameter(ptr);
+                }
+            }
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let source = read_to_string(path).map_err(|e| {
+            nih_error!("Failed to read preset {}: {}", path.display(), e);
+            Error::PresetReadFailed
+        })?;
+        serde_json::from_str(&source).map_err(|e| {
+            nih_error!("Failed to parse preset {}: {}", path.display(), e);
+            Error::PresetParseFailed
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let source = serde_json::to_string_pretty(self).map_err(|e| {
+            nih_error!("Failed to serialize preset: {}", e);
+            Error::PresetWriteFailed
+        })?;
+        write(path, source).map_err(|e| {
+            nih_error!("Failed to write preset {}: {}", path.display(), e);
+            Error::PresetWriteFailed
+        })
+    }
+}
+
+/// Directory of presets saved from the editor
+pub struct PresetLibrary {
+    dir: PathBuf,
+}
+
+impl PresetLibrary {
+    pub fn open() -> Result<Self> {
+        let dir = config_dir()
+            .get_or(Error::PresetDirUnavailable)?
+            .join(PACKAGE_NAME)
+            .join("presets");
+        create_dir_all(&dir).map_err(|e| {
+            nih_error!("Failed to create preset directory {}: {}", dir.display(), e);
+            Error::PresetDirUnavailable
+        })?;
+        Ok(Self { dir })
+    }
+
+    /// Names of all presets in alphabetical order
+    pub fn names(&self) -> Result<Vec<String>> {
+        let entries = read_dir(&self.dir).map_err(|e| {
+            nih_error!("Failed to list presets in {}: {}", self.dir.display(), e);
+            Error::PresetReadFailed
+        })?;
+
+        let mut names = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some(PRESET_EXTENSION))
+            .filter_map(|path| {
+                path.file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .map(String::from)
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn load(&self, name: &str) -> Result<Preset> {
+        Preset::read(&self.path(name)?)
+    }
+
+    pub fn save(&self, preset: &Preset) -> Result<()> {
+        preset.write(&self.path(&preset.name)?)
+    }
+
+    pub fn rename(&self, old_name: &str, new_name: &str) -> Result<()> {
+        let old_path = self.path(old_name)?;
+        let new_path = self.path(new_name)?;
+
+        // Renames that only change case refer to the same file on
+        // case-insensitive file systems
+        if !old_name.eq_ignore_ascii_case(new_name) && new_path.exists() {
+            return Err(Error::PresetAlreadyExists);
+        }
+
+        rename(&old_path, &new_path).map_err(|e| {
+            nih_error!("Failed to rename preset {}: {}", old_path.display(), e);
+            Error::PresetWriteFailed
+        })?;
+
+        let mut preset = Preset::read(&new_path)?;
+        preset.name = String::from(new_name);
+        preset.write(&new_path)
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        validate_name(name)?;
+        Ok(self.dir.join(name).with_extension(PRESET_EXTENSION))
+    }
+}
+
+/// Preset names are used as file names so must be valid on all platforms
+fn validate_name(name: &str) -> Result<()> {
+    const INVALID_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+    if name.trim().is_empty()
+        || name != name.trim()
+        || name.starts_with('.')
+        || name.contains(INVALID_CHARS)
+        || name.chars().any(char::is_control)
+    {
+        Err(Error::InvalidPresetName)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn config_dir() -> Option<PathBuf> {
+    var_os("APPDATA").map(PathBuf::from)
+}
+
+#[cfg(target_os = "macos")]
+fn config_dir() -> Option<PathBuf> {
+    var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn config_dir() -> Option<PathBuf> {
+    var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
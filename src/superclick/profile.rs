@@ -0,0 +1,133 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::click::{Click, Tier};
+use super::profile_file::ProfileFile;
+use crate::result::Result;
+use nih_plug::prelude::Enum;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::RwLock;
+
+const BODY_BEAT_PULSE_SOURCE: &str = include_str!("../../profiles/body-beat-pulse.toml");
+const AUDIBLE_SOURCE: &str = include_str!("../../profiles/audible.toml");
+
+/// Device that clicks are generated for
+#[derive(Clone, Copy, Debug, Default, Enum, Eq, PartialEq)]
+pub enum DeviceProfile {
+    #[default]
+    #[name = "Body Beat Pulse"]
+    BodyBeatPulse,
+    Audible,
+
+    /// Profile loaded from disk
+    Custom,
+}
+
+/// Clicks to generate for each tier
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct Profile {
+    pub accent: Click,
+    pub subaccent: Click,
+    pub normal: Click,
+}
+
+impl Profile {
+    pub fn click(&self, tier: Tier) -> &Click {
+        match tier {
+            Tier::Accent => &self.accent,
+            Tier::Subaccent => &self.subaccent,
+            Tier::Normal => &self.normal,
+        }
+    }
+}
+
+/// Named profile loaded from disk and persisted with the plugin state
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CustomProfile {
+    pub name: String,
+    pub profile: Profile,
+}
+
+impl CustomProfile {
+    pub fn load(path: &Path) -> Result<Self> {
+        ProfileFile::read(path)?.validate()
+    }
+}
+
+/// Profiles embedded in the plugin
+pub struct BuiltInProfiles {
+    body_beat_pulse: Profile,
+    audible: Profile,
+}
+
+impl BuiltInProfiles {
+    /// Profile to use for the given selection: falls back to the Body
+    /// Beat Pulse profile if no custom profile is available
+    ///
+    /// Does not block or allocate so is safe to call from the audio thread
+    pub fn select(
+        &self,
+        device_profile: DeviceProfile,
+        custom_profile: &RwLock<Option<CustomProfile>>,
+    ) -> Profile {
+        match device_profile {
+            DeviceProfile::BodyBeatPulse => self.body_beat_pulse,
+            DeviceProfile::Audible => self.audible,
+            DeviceProfile::Custom => custom_profile
+                .try_read()
+                .ok()
+                .and_then(|custom_profile| custom_profile.as_ref().map(|c| c.profile))
+                .unwrap_or(self.body_beat_pulse),
+        }
+    }
+
+    fn parse(source: &str) -> Profile {
+        ProfileFile::parse_toml(source)
+            .and_then(ProfileFile::validate)
+            .expect("built-in profile must be valid")
+            .profile
+    }
+}
+
+impl Default for BuiltInProfiles {
+    fn default() -> Self {
+        Self {
+            body_beat_pulse: Self::parse(BODY_BEAT_PULSE_SOURCE),
+            audible: Self::parse(AUDIBLE_SOURCE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BuiltInProfiles, ProfileFile, AUDIBLE_SOURCE, BODY_BEAT_PULSE_SOURCE};
+
+    #[test]
+    fn built_in_profiles_are_valid() {
+        for source in [BODY_BEAT_PULSE_SOURCE, AUDIBLE_SOURCE] {
+            assert!(ProfileFile::parse_toml(source)
+                .and_then(ProfileFile::validate)
+                .is_ok());
+        }
+        BuiltInProfiles::default();
+    }
+}
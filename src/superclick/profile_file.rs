@@ -0,0 +1,308 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::click::{Channel, Click, Tier, Waveform};
+use super::profile::{CustomProfile, Profile};
+use crate::error::Error;
+use crate::result::Result;
+use nih_plug::{nih_error, util};
+use serde::Deserialize;
+use std::fs::read_to_string;
+use std::path::Path;
+
+const MAX_FREQUENCY: f32 = 20_000f32;
+const MAX_LENGTH_CROTCHETS: f64 = 4f64;
+const MIN_LEVEL_DB: f32 = -60f32;
+const MAX_LEVEL_DB: f32 = 12f32;
+
+/// Device profile as stored in a TOML or JSON file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileFile {
+    name: String,
+    accent: ClickFile,
+    subaccent: ClickFile,
+    normal: ClickFile,
+}
+
+/// A single tier's click as stored in a profile file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClickFile {
+    /// Frequency in Hz
+    frequency: f32,
+
+    /// Length in crotchets
+    length: f64,
+
+    #[serde(default = "ClickFile::default_waveform")]
+    waveform: Waveform,
+
+    #[serde(default = "ClickFile::default_channel")]
+    channel: Channel,
+
+    /// Level in dB
+    #[serde(default)]
+    level: f32,
+}
+
+impl ProfileFile {
+    /// Reads a profile from a `.toml` or `.json` file
+    pub fn read(path: &Path) -> Result<Self> {
+        let parse: fn(&str) -> Result<Self> = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::parse_toml,
+            Some("json") => Self::parse_json,
+            _ => return Err(Error::UnsupportedProfileFormat),
+        };
+
+        let source = read_to_string(path).map_err(|e| {
+            nih_error!("Failed to read profile {}: {}", path.display(), e);
+            Error::ProfileReadFailed
+        })?;
+        parse(&source)
+    }
+
+    pub fn parse_toml(source: &str) -> Result<Self> {
+        toml::from_str(source).map_err(|e| {
+            nih_error!("Failed to parse profile: {}", e);
+            Error::ProfileParseFailed(e.to_string())
+        })
+    }
+
+    pub fn parse_json(source: &str) -> Result<Self> {
+        serde_json::from_str(source).map_err(|e| {
+            nih_error!("Failed to parse profile: {}", e);
+            Error::ProfileParseFailed(e.to_string())
+        })
+    }
+
+    pub fn validate(self) -> Result<CustomProfile> {
+        if self.name.trim().is_empty() {
+            return Err(Error::InvalidProfileName);
+        }
+
+        Ok(CustomProfile {
+            profile: Profile {
+                accent: self.accent.validate(Tier::Accent)?,
+                subaccent: self.subaccent.validate(Tier::Subaccent)?,
+                normal: self.normal.validate(Tier::Normal)?,
+            },
+            name: self.name,
+        })
+    }
+}
+
+impl ClickFile {
+    fn default_waveform() -> Waveform {
+        Waveform::Sine
+    }
+
+    fn default_channel() -> Channel {
+        Channel::Both
+    }
+
+    fn validate(&self, tier: Tier) -> Result<Click> {
+        if !self.frequency.is_finite() || self.frequency <= 0f32 || self.frequency > MAX_FREQUENCY {
+            return Err(Error::InvalidProfileFrequency(tier));
+        }
+
+        if !self.length.is_finite() || self.length <= 0f64 || self.length > MAX_LENGTH_CROTCHETS {
+            return Err(Error::InvalidProfileLength(tier));
+        }
+
+        if !self.level.is_finite() || !(MIN_LEVEL_DB..=MAX_LEVEL_DB).contains(&self.level) {
+            return Err(Error::InvalidProfileLevel(tier));
+        }
+
+        Ok(Click {
+            channel: self.channel,
+            waveform: self.waveform,
+            frequency: self.frequency,
+            length: self.length,
+            level: util::db_to_gain(self.level),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProfileFile, Tier};
+    use crate::error::Error;
+    use crate::result::Result;
+    use std::path::Path;
+
+    const VALID: &str = r#"
+name = "Test"
+
+[accent]
+frequency = 1760.0
+length = 0.0625
+waveform = "square"
+channel = "left"
+level = -3.0
+
+[subaccent]
+frequency = 1320.0
+length = 0.0625
+
+[normal]
+frequency = 880.0
+length = 0.0625
+"#;
+
+    fn parse(source: &str) -> Result<ProfileFile> {
+        ProfileFile::parse_toml(source)
+    }
+
+    fn validate(source: &str) -> Result<()> {
+        parse(source)?.validate().map(|_| ())
+    }
+
+    #[test]
+    fn read_built_in_profiles() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("profiles");
+        for file_name in ["audible.toml", "body-beat-pulse.toml"] {
+            let profile = ProfileFile::read(&dir.join(file_name))
+                .and_then(ProfileFile::validate)
+                .unwrap();
+            assert!(!profile.name.is_empty());
+        }
+    }
+
+    #[test]
+    fn parse_toml_and_json() {
+        let profile = parse(VALID).unwrap().validate().unwrap();
+        assert_eq!("Test", profile.name);
+        assert_eq!(1760f32, profile.profile.accent.frequency);
+        assert!((profile.profile.accent.level - 0.70795f32).abs() < 1e-4f32);
+        assert_eq!(1f32, profile.profile.normal.level);
+
+        let profile = ProfileFile::parse_json(
+            r#"{
+                "name": "Test",
+                "accent": { "frequency": 1760.0, "length": 0.0625 },
+                "subaccent": { "frequency": 1320.0, "length": 0.0625 },
+                "normal": { "frequency": 880.0, "length": 0.0625, "waveform": "saw" }
+            }"#,
+        )
+        .unwrap()
+        .validate()
+        .unwrap();
+        assert_eq!(880f32, profile.profile.normal.frequency);
+    }
+
+    #[test]
+    fn invalid_name() {
+        let source = VALID.replace("name = \"Test\"", "name = \" \"");
+        assert!(matches!(validate(&source), Err(Error::InvalidProfileName)));
+    }
+
+    #[test]
+    fn invalid_frequency() {
+        for frequency in ["0.0", "-440.0", "20001.0", "nan", "inf"] {
+            let source = VALID.replace("1320.0", frequency);
+            assert!(
+                matches!(
+                    validate(&source),
+                    Err(Error::InvalidProfileFrequency(Tier::Subaccent))
+                ),
+                "{}",
+                frequency
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_length() {
+        for length in ["0.0", "-1.0", "4.5", "nan"] {
+            let source = VALID.replacen("0.0625", length, 1);
+            assert!(
+                matches!(
+                    validate(&source),
+                    Err(Error::InvalidProfileLength(Tier::Accent))
+                ),
+                "{}",
+                length
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_level() {
+        for level in ["-61.0", "12.5", "nan"] {
+            let source = VALID.replace("-3.0", level);
+            assert!(
+                matches!(
+                    validate(&source),
+                    Err(Error::InvalidProfileLevel(Tier::Accent))
+                ),
+                "{}",
+                level
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_fields() {
+        let source = VALID.replace("[normal]", "[normal]\nvolume = 1.0");
+        match parse(&source) {
+            Err(Error::ProfileParseFailed(message)) => assert!(message.contains("volume")),
+            _ => panic!("unknown field accepted"),
+        }
+
+        let source = format!("colour = \"red\"\n{}", VALID);
+        assert!(matches!(parse(&source), Err(Error::ProfileParseFailed(_))));
+    }
+
+    #[test]
+    fn invalid_values() {
+        for (from, to) in [
+            ("\"square\"", "\"sawtooth\""),
+            ("\"left\"", "\"centre\""),
+            ("frequency = 880.0\n", ""),
+        ] {
+            let source = VALID.replace(from, to);
+            assert!(
+                matches!(parse(&source), Err(Error::ProfileParseFailed(_))),
+                "{}",
+                to
+            );
+        }
+    }
+
+    #[test]
+    fn unsupported_format() {
+        for path in ["profile.yaml", "profile", "profile.TOML"] {
+            assert!(
+                matches!(
+                    ProfileFile::read(Path::new(path)),
+                    Err(Error::UnsupportedProfileFormat)
+                ),
+                "{}",
+                path
+            );
+        }
+        assert!(matches!(
+            ProfileFile::read(Path::new("missing.toml")),
+            Err(Error::ProfileReadFailed)
+        ));
+    }
+}
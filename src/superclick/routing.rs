@@ -0,0 +1,240 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::click::Channel;
+use super::params::new_gain_param;
+use super::profile::DeviceProfile;
+use nih_plug::prelude::*;
+
+/// Maximum number of main output channels that clicks can be routed to
+pub const MAX_CHANNELS: usize = 4;
+
+/// Number of auxiliary output buses
+pub const AUX_BUS_COUNT: usize = 3;
+
+const LEFT_CHANNEL_ID: usize = 0;
+const RIGHT_CHANNEL_ID: usize = 1;
+
+/// Output channel or channels a click tier is sent to
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum Output {
+    /// Channels used by the device profile to encode the click tier
+    Device,
+    Both,
+    Left,
+    Right,
+    #[name = "Channel 3"]
+    Channel3,
+    #[name = "Channel 4"]
+    Channel4,
+    All,
+}
+
+/// Output bus a click tier is sent to
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum Bus {
+    Main,
+    #[name = "Aux 1"]
+    Aux1,
+    #[name = "Aux 2"]
+    Aux2,
+    #[name = "Aux 3"]
+    Aux3,
+}
+
+impl Bus {
+    /// Index into the plugin's auxiliary outputs or `None` for the main
+    /// output
+    pub fn aux_index(&self) -> Option<usize> {
+        match self {
+            Self::Main => None,
+            Self::Aux1 => Some(0),
+            Self::Aux2 => Some(1),
+            Self::Aux3 => Some(2),
+        }
+    }
+}
+
+/// Device profile that an auxiliary bus plays every click tier with in
+/// addition to the clicks routed to it
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum Mirror {
+    Off,
+    #[name = "Body Beat Pulse"]
+    BodyBeatPulse,
+    Audible,
+    Custom,
+}
+
+impl Mirror {
+    pub fn device_profile(&self) -> Option<DeviceProfile> {
+        match self {
+            Self::Off => None,
+            Self::BodyBeatPulse => Some(DeviceProfile::BodyBeatPulse),
+            Self::Audible => Some(DeviceProfile::Audible),
+            Self::Custom => Some(DeviceProfile::Custom),
+        }
+    }
+}
+
+/// Mirror for each auxiliary bus so that, for example, an audible click on
+/// the main output can be accompanied by haptic pulses on another mix
+#[derive(Params)]
+pub struct MirrorParams {
+    #[id = "aux1"]
+    pub aux1: EnumParam<Mirror>,
+
+    #[id = "aux2"]
+    pub aux2: EnumParam<Mirror>,
+
+    #[id = "aux3"]
+    pub aux3: EnumParam<Mirror>,
+}
+
+impl Default for MirrorParams {
+    fn default() -> Self {
+        Self {
+            aux1: EnumParam::new("Aux 1 mirror", Mirror::Off),
+            aux2: EnumParam::new("Aux 2 mirror", Mirror::Off),
+            aux3: EnumParam::new("Aux 3 mirror", Mirror::Off),
+        }
+    }
+}
+
+impl MirrorParams {
+    /// Mirror of each auxiliary bus indexed as by `Bus::aux_index`
+    pub fn mirrors(&self) -> [Mirror; AUX_BUS_COUNT] {
+        [self.aux1.value(), self.aux2.value(), self.aux3.value()]
+    }
+}
+
+/// Routing of a single click tier
+#[derive(Params)]
+pub struct RoutingParams {
+    #[id = "bus"]
+    pub bus: EnumParam<Bus>,
+
+    #[id = "output"]
+    pub output: EnumParam<Output>,
+
+    #[id = "gain"]
+    pub gain: FloatParam,
+
+    #[id = "pan"]
+    pub pan: FloatParam,
+}
+
+impl Default for RoutingParams {
+    fn default() -> Self {
+        Self {
+            bus: EnumParam::new("Bus", Bus::Main),
+            output: EnumParam::new("Output", Output::Device),
+            gain: new_gain_param("Gain"),
+            pan: FloatParam::new(
+                "Pan",
+                0f32,
+                FloatRange::Linear {
+                    min: -1f32,
+                    max: 1f32,
+                },
+            )
+            .with_value_to_string(formatters::v2s_f32_panning())
+            .with_string_to_value(formatters::s2v_f32_panning()),
+        }
+    }
+}
+
+impl RoutingParams {
+    /// Gains to apply to each channel of a layout with the given number
+    /// of channels
+    pub fn channel_gains(&self, channel_count: usize, device_channel: Channel) -> ChannelGains {
+        let gain = self.gain.value();
+        let mut gains = ChannelGains::default();
+
+        // Mono layouts fold everything down to the only channel so that,
+        // for example, a right-only accent is still audible
+        if channel_count < 2 {
+            gains.set(LEFT_CHANNEL_ID, gain);
+            return gains;
+        }
+
+        let pan = self.pan.value();
+        let set_both = |gains: &mut ChannelGains| {
+            gains.set(LEFT_CHANNEL_ID, gain * (1f32 - pan).min(1f32));
+            gains.set(RIGHT_CHANNEL_ID, gain * (1f32 + pan).min(1f32));
+        };
+
+        match self.output.value() {
+            Output::Device => match device_channel {
+                Channel::Left => gains.set(LEFT_CHANNEL_ID, gain),
+                Channel::Right => gains.set(RIGHT_CHANNEL_ID, gain),
+                Channel::Both => set_both(&mut gains),
+            },
+            Output::Both => set_both(&mut gains),
+            Output::Left => gains.set(LEFT_CHANNEL_ID, gain),
+            Output::Right => gains.set(RIGHT_CHANNEL_ID, gain),
+            Output::Channel3 if channel_count > 2 => gains.set(2, gain),
+            Output::Channel4 if channel_count > 3 => gains.set(3, gain),
+            // Channels that don't exist in this layout fall back to the
+            // main stereo pair
+            Output::Channel3 | Output::Channel4 => set_both(&mut gains),
+            Output::All => {
+                for channel_id in 0..channel_count.min(MAX_CHANNELS) {
+                    gains.set(channel_id, gain);
+                }
+            }
+        }
+
+        gains
+    }
+}
+
+/// Per-channel gains for a click tier: channels without a gain are not
+/// written to
+#[derive(Clone, Copy, Default)]
+pub struct ChannelGains([Option<f32>; MAX_CHANNELS]);
+
+impl ChannelGains {
+    pub fn get(&self, channel_id: usize) -> Option<f32> {
+        self.0.get(channel_id).copied().flatten()
+    }
+
+    pub fn scaled(&self, level: f32) -> Self {
+        Self(self.0.map(|gain| gain.map(|gain| gain * level)))
+    }
+
+    /// Adds a block of values to each channel with a gain from the given
+    /// sample
+    pub fn mix(&self, channels: &mut [&mut [f32]], start: usize, values: &[f32]) {
+        for (channel_id, channel) in channels.iter_mut().enumerate() {
+            if let Some(gain) = self.get(channel_id) {
+                let samples = &mut channel[start..start + values.len()];
+                for (sample, value) in samples.iter_mut().zip(values) {
+                    *sample += value * gain;
+                }
+            }
+        }
+    }
+
+    fn set(&mut self, channel_id: usize, gain: f32) {
+        self.0[channel_id] = Some(gain);
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::error::Error;
+use crate::result::Result;
+use hound::{SampleFormat, WavReader};
+use nih_plug::nih_error;
+use std::io::Read;
+use std::path::Path;
+
+/// Mono audio sample loaded into memory
+pub struct Sample {
+    sample_rate: f64,
+    data: Vec<f32>,
+}
+
+impl Sample {
+    /// Reads a WAV file mixing all channels down to mono
+    pub fn read(path: &Path) -> Result<Self> {
+        let reader = WavReader::open(path).map_err(|e| {
+            nih_error!("Failed to open sample {}: {}", path.display(), e);
+            Error::SampleReadFailed
+        })?;
+
+        Self::decode(reader).map_err(|e| {
+            nih_error!("Failed to read sample {}: {}", path.display(), e);
+            Error::SampleReadFailed
+        })
+    }
+
+    /// Parses a WAV file held in memory
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        WavReader::new(bytes).and_then(Self::decode).map_err(|e| {
+            nih_error!("Failed to parse sample: {}", e);
+            Error::SampleReadFailed
+        })
+    }
+
+    fn decode<R: Read>(mut reader: WavReader<R>) -> hound::Result<Self> {
+        let spec = reader.spec();
+        let frames = match spec.sample_format {
+            SampleFormat::Float => reader
+                .samples::<f32>()
+                .collect::<std::result::Result<Vec<_>, _>>(),
+            SampleFormat::Int => {
+                let scale = 1f32 / (1u32 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.map(|s| s as f32 * scale))
+                    .collect::<std::result::Result<Vec<_>, _>>()
+            }
+        }?;
+
+        let channels = spec.channels.max(1) as usize;
+        let data = frames
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+
+        Ok(Self {
+            sample_rate: spec.sample_rate as f64,
+            data,
+        })
+    }
+
+    /// Length in seconds
+    pub fn duration(&self) -> f64 {
+        self.data.len() as f64 / self.sample_rate
+    }
+
+    /// Value at the given time in seconds using linear interpolation:
+    /// silent outside the sample
+    pub fn value_at(&self, seconds: f64) -> f32 {
+        if seconds < 0f64 {
+            return 0f32;
+        }
+
+        let position = seconds * self.sample_rate;
+        let index = position.trunc() as usize;
+        match (self.data.get(index), self.data.get(index + 1)) {
+            (Some(&a), Some(&b)) => a + (b - a) * position.fract() as f32,
+            (Some(&a), None) => a,
+            _ => 0f32,
+        }
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::error::Error;
+use crate::music_theory::{Grouping, Meter, TimeSignatureBottom, TimeSignatureTop};
+use crate::result::Result;
+use nih_plug::nih_error;
+use serde::{Deserialize, Serialize};
+use std::fs::read_to_string;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const MIN_TEMPO: f64 = 20f64;
+const MAX_TEMPO: f64 = 400f64;
+const MAX_COUNT_IN_BARS: i32 = 8;
+
+/// MIDI note that moves to the previous song (C3)
+pub const PREVIOUS_SONG_NOTE: u8 = 48;
+
+/// MIDI note that moves to the next song (D3)
+pub const NEXT_SONG_NOTE: u8 = 50;
+
+/// Ordered list of songs loaded from a TOML file
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Setlist {
+    pub songs: Vec<SetlistEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SetlistEntry {
+    pub name: String,
+
+    /// Name of the preset applied when the song becomes current
+    #[serde(default)]
+    pub preset: Option<String>,
+
+    #[serde(flatten)]
+    pub song: Song,
+}
+
+/// Click settings for a single song
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct Song {
+    /// Tempo in crotchets per minute used by the internal clock
+    #[serde(default)]
+    pub tempo: Option<f64>,
+
+    /// Tempo in beats per minute of the clock's beat unit used instead of
+    /// `tempo`
+    #[serde(default)]
+    pub bpm: Option<f64>,
+
+    pub time_signature_top: TimeSignatureTop,
+    pub time_signature_bottom: TimeSignatureBottom,
+
+    /// Grouping of beats into accented groups overriding the meter's
+    /// default accents
+    #[serde(default)]
+    pub accent_pattern: Option<Grouping>,
+
+    /// Bars of click before the song starts when using the internal clock
+    #[serde(default)]
+    pub count_in_bars: i32,
+}
+
+impl Song {
+    /// Tempo in crotchets per minute given the meter whose beat unit `bpm`
+    /// is expressed in
+    pub fn crotchets_per_minute(&self, meter: &Meter) -> f64 {
+        match (self.tempo, self.bpm) {
+            (Some(tempo), _) => tempo,
+            (None, Some(bpm)) => bpm * meter.beat_length_crotchets(),
+            (None, None) => 0f64,
+        }
+    }
+
+    pub fn meter(&self) -> Result<Meter> {
+        let meter = Meter::new(self.time_signature_top, self.time_signature_bottom);
+        match self.accent_pattern {
+            Some(accent_pattern) => meter.with_grouping(accent_pattern),
+            None => Ok(meter),
+        }
+    }
+}
+
+impl Setlist {
+    pub fn read(path: &Path) -> Result<Self> {
+        let source = read_to_string(path).map_err(|e| {
+            nih_error!("Failed to read setlist {}: {}", path.display(), e);
+            Error::SetlistReadFailed
+        })?;
+        let setlist = toml::from_str::<Self>(&source).map_err(|e| {
+            nih_error!("Failed to parse setlist {}: {}", path.display(), e);
+            Error::SetlistParseFailed
+        })?;
+        setlist.validate()?;
+        Ok(setlist)
+    }
+
+    /// Current song at the given position if there is one
+    pub fn song(&self, position: &AtomicUsize) -> Option<Song> {
+        self.songs
+            .get(position.load(Ordering::Relaxed))
+            .map(|entry| entry.song)
+    }
+
+    /// Moves to the next song or previous song stopping at either end of
+    /// the setlist
+    pub fn step(&self, position: &AtomicUsize, forward: bool) {
+        if let Some(last) = self.songs.len().checked_sub(1) {
+            let current = position.load(Ordering::Relaxed).min(last);
+            let new = if forward {
+                (current + 1).min(last)
+            } else {
+                current.saturating_sub(1)
+            };
+            position.store(new, Ordering::Relaxed);
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        for entry in &self.songs {
+            if entry.name.trim().is_empty() {
+                return Err(Error::InvalidSongName);
+            }
+
+            // Each song has exactly one of a tempo and a bpm
+            let song = &entry.song;
+            match (song.tempo, song.bpm) {
+                (Some(tempo), None) | (None, Some(tempo))
+                    if (MIN_TEMPO..=MAX_TEMPO).contains(&tempo) => {}
+                _ => return Err(Error::InvalidSongTempo),
+            }
+
+            song.meter()?;
+
+            if !(0..=MAX_COUNT_IN_BARS).contains(&song.count_in_bars) {
+                return Err(Error::InvalidSongCountIn);
+            }
+        }
+
+        Ok(())
+    }
+}
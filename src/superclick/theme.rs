@@ -0,0 +1,144 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use super::click::Tier;
+use nih_plug_iced::container::{Style, StyleSheet};
+use nih_plug_iced::{Background, Color};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Editor color theme
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+    HighContrast,
+    ColorblindSafe,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 4] = [
+        Theme::Light,
+        Theme::Dark,
+        Theme::HighContrast,
+        Theme::ColorblindSafe,
+    ];
+
+    pub fn palette(&self) -> Palette {
+        match self {
+            Self::Light => Palette {
+                background: rgb(0.58, 0.98, 0.58),
+                text: rgb(0.0, 0.0, 0.0),
+                error: rgb(0.7, 0.0, 0.0),
+                lamp_off: rgb(0.4, 0.65, 0.4),
+                lamp_accent: rgb(0.85, 0.1, 0.1),
+                lamp_subaccent: rgb(0.95, 0.55, 0.0),
+                lamp_normal: rgb(0.1, 0.3, 0.9),
+            },
+            Self::Dark => Palette {
+                background: rgb(0.1, 0.1, 0.12),
+                text: rgb(0.85, 0.85, 0.85),
+                error: rgb(1.0, 0.4, 0.4),
+                lamp_off: rgb(0.25, 0.25, 0.28),
+                lamp_accent: rgb(0.95, 0.25, 0.2),
+                lamp_subaccent: rgb(0.95, 0.7, 0.2),
+                lamp_normal: rgb(0.3, 0.8, 0.4),
+            },
+            Self::HighContrast => Palette {
+                background: rgb(0.0, 0.0, 0.0),
+                text: rgb(1.0, 1.0, 1.0),
+                error: rgb(1.0, 1.0, 0.0),
+                lamp_off: rgb(0.2, 0.2, 0.2),
+                lamp_accent: rgb(1.0, 0.0, 0.0),
+                lamp_subaccent: rgb(1.0, 1.0, 0.0),
+                lamp_normal: rgb(1.0, 1.0, 1.0),
+            },
+            // Okabe-Ito colors: distinguishable with all common forms of
+            // color vision deficiency
+            Self::ColorblindSafe => Palette {
+                background: rgb(0.12, 0.12, 0.12),
+                text: rgb(0.95, 0.95, 0.95),
+                error: rgb(0.94, 0.89, 0.26),
+                lamp_off: rgb(0.3, 0.3, 0.3),
+                lamp_accent: rgb(0.84, 0.37, 0.0),
+                lamp_subaccent: rgb(0.94, 0.89, 0.26),
+                lamp_normal: rgb(0.34, 0.71, 0.91),
+            },
+        }
+    }
+}
+
+impl Display for Theme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let s = match self {
+            Self::Light => "Light",
+            Self::Dark => "Dark",
+            Self::HighContrast => "High contrast",
+            Self::ColorblindSafe => "Colorblind safe",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Colors used by the editor for a given theme
+#[derive(Clone, Copy)]
+pub struct Palette {
+    pub background: Color,
+    pub text: Color,
+    pub error: Color,
+    pub lamp_off: Color,
+    pub lamp_accent: Color,
+    pub lamp_subaccent: Color,
+    pub lamp_normal: Color,
+}
+
+impl Palette {
+    pub fn lamp(&self, tier: Tier) -> Color {
+        match tier {
+            Tier::Accent => self.lamp_accent,
+            Tier::Subaccent => self.lamp_subaccent,
+            Tier::Normal => self.lamp_normal,
+        }
+    }
+}
+
+/// Style for a single beat lamp
+pub struct LampStyle {
+    pub color: Color,
+    pub border: Color,
+}
+
+impl StyleSheet for LampStyle {
+    fn style(&self) -> Style {
+        Style {
+            background: Some(Background::Color(self.color)),
+            border_radius: 4.0,
+            border_width: 1.0,
+            border_color: self.border,
+            ..Style::default()
+        }
+    }
+}
+
+const fn rgb(r: f32, g: f32, b: f32) -> Color {
+    Color { r, g, b, a: 1.0 }
+}
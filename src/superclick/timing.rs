@@ -0,0 +1,141 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use nih_plug::prelude::*;
+
+/// Largest timing offset in either direction in milliseconds
+const MAX_OFFSET_MS: f32 = 200f32;
+
+/// Largest timing offset in either direction in samples
+const MAX_OFFSET_SAMPLES: i32 = 9600;
+
+/// Longest lookahead in milliseconds
+const MAX_LOOKAHEAD_MS: f32 = 200f32;
+
+/// Parameters for shifting clicks in time to compensate for the latency of
+/// in-ear monitors and haptic devices
+#[derive(Params)]
+pub struct TimingParams {
+    #[id = "offset"]
+    pub offset: FloatParam,
+
+    #[id = "offset-samples"]
+    pub offset_samples: IntParam,
+
+    #[id = "lookahead"]
+    pub lookahead: FloatParam,
+}
+
+impl Default for TimingParams {
+    fn default() -> Self {
+        Self {
+            offset: FloatParam::new(
+                "Timing offset",
+                0f32,
+                FloatRange::Linear {
+                    min: -MAX_OFFSET_MS,
+                    max: MAX_OFFSET_MS,
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1f32),
+            offset_samples: IntParam::new(
+                "Timing offset (samples)",
+                0,
+                IntRange::Linear {
+                    min: -MAX_OFFSET_SAMPLES,
+                    max: MAX_OFFSET_SAMPLES,
+                },
+            )
+            .with_unit(" samples"),
+            lookahead: FloatParam::new(
+                "Lookahead",
+                0f32,
+                FloatRange::Linear {
+                    min: 0f32,
+                    max: MAX_LOOKAHEAD_MS,
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1f32),
+        }
+    }
+}
+
+impl TimingParams {
+    /// Total offset in samples: positive offsets make clicks later and
+    /// negative offsets make them earlier
+    pub fn offset_samples(&self, sample_rate: f32) -> i64 {
+        ms_to_samples(self.offset.value(), sample_rate) + self.offset_samples.value() as i64
+    }
+
+    /// Latency reported to the host so that it delivers the transport early
+    pub fn latency_samples(&self, sample_rate: f32) -> u32 {
+        ms_to_samples(self.lookahead.value(), sample_rate) as u32
+    }
+}
+
+/// Delays the dry signal by the lookahead so that it stays aligned with the
+/// rest of the host's mix
+#[derive(Default)]
+pub struct DryDelay {
+    channels: Vec<Vec<f32>>,
+    index: usize,
+}
+
+impl DryDelay {
+    /// Allocates enough memory for the longest lookahead
+    pub fn initialize(&mut self, channel_count: usize, sample_rate: f32) {
+        let length = ms_to_samples(MAX_LOOKAHEAD_MS, sample_rate) as usize + 1;
+        self.channels = vec![vec![0f32; length]; channel_count];
+        self.index = 0;
+    }
+
+    /// Delays each channel of the buffer by the given number of samples
+    pub fn process(&mut self, buffer: &mut Buffer, delay_samples: u32) {
+        let length = match self.channels.first() {
+            Some(channel) => channel.len(),
+            None => return,
+        };
+        let delay_samples = (delay_samples as usize).min(length - 1);
+        let mut index = self.index;
+        for (channel, history) in buffer.as_slice().iter_mut().zip(&mut self.channels) {
+            index = self.index;
+            for sample in channel.iter_mut() {
+                history[index] = *sample;
+                *sample = history[(index + length - delay_samples) % length];
+                index = (index + 1) % length;
+            }
+        }
+        self.index = index;
+    }
+
+    pub fn reset(&mut self) {
+        for channel in self.channels.iter_mut() {
+            channel.fill(0f32);
+        }
+        self.index = 0;
+    }
+}
+
+fn ms_to_samples(ms: f32, sample_rate: f32) -> i64 {
+    (ms as f64 * sample_rate as f64 / 1000f64).round() as i64
+}
@@ -0,0 +1,165 @@
+// Copyright (c) 2024 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use nih_plug::prelude::*;
+
+/// Tempo ramp applied to the internal clock for practice
+#[derive(Params)]
+pub struct TrainerParams {
+    #[id = "enabled"]
+    pub enabled: BoolParam,
+
+    #[id = "start-tempo"]
+    pub start_tempo: FloatParam,
+
+    #[id = "end-tempo"]
+    pub end_tempo: FloatParam,
+
+    #[id = "step"]
+    pub step: FloatParam,
+
+    #[id = "bars"]
+    pub bars: IntParam,
+
+    #[id = "loop"]
+    pub is_looping: BoolParam,
+}
+
+impl Default for TrainerParams {
+    fn default() -> Self {
+        Self {
+            enabled: BoolParam::new("Speed trainer", false),
+            start_tempo: new_tempo_param("Start tempo", 80f32),
+            end_tempo: new_tempo_param("End tempo", 120f32),
+            step: FloatParam::new(
+                "Tempo step",
+                5f32,
+                FloatRange::Linear {
+                    min: 0.1f32,
+                    max: 50f32,
+                },
+            )
+            .with_unit(" qpm")
+            .with_step_size(0.1f32),
+            bars: IntParam::new("Bars per step", 4, IntRange::Linear { min: 1, max: 64 })
+                .with_unit(" bars"),
+            is_looping: BoolParam::new("Loop", false),
+        }
+    }
+}
+
+impl TrainerParams {
+    pub fn trainer(&self) -> Option<Trainer> {
+        if self.enabled.value() {
+            Some(Trainer {
+                start_tempo: self.start_tempo.value() as f64,
+                end_tempo: self.end_tempo.value() as f64,
+                step: self.step.value() as f64,
+                bars: self.bars.value(),
+                is_looping: self.is_looping.value(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Tempo ramp from a start tempo to an end tempo in equal steps every given
+/// number of bars
+#[derive(Clone, Copy, Debug)]
+pub struct Trainer {
+    start_tempo: f64,
+    end_tempo: f64,
+    step: f64,
+    bars: i32,
+    is_looping: bool,
+}
+
+/// Position within a tempo ramp
+#[derive(Clone, Copy, Debug)]
+pub struct TrainerProgress {
+    /// Zero-based index of the current step
+    pub step: i32,
+    pub step_count: i32,
+    pub tempo: f64,
+
+    /// Tempo of the next step unless the ramp has finished
+    pub next_tempo: Option<f64>,
+    pub bars_to_next: i32,
+}
+
+impl Trainer {
+    /// Progress at the given bar: count-in bars play at the start tempo
+    pub fn progress(&self, bar_number: i32) -> TrainerProgress {
+        let bar_number = bar_number.max(0);
+        let step_count = self.step_count();
+        let step = if self.is_looping {
+            (bar_number / self.bars) % step_count
+        } else {
+            (bar_number / self.bars).min(step_count - 1)
+        };
+
+        let next_step = if step + 1 < step_count {
+            Some(step + 1)
+        } else if self.is_looping {
+            Some(0)
+        } else {
+            None
+        };
+
+        TrainerProgress {
+            step,
+            step_count,
+            tempo: self.tempo_at_step(step),
+            next_tempo: next_step.map(|step| self.tempo_at_step(step)),
+            bars_to_next: self.bars - bar_number % self.bars,
+        }
+    }
+
+    /// Number of steps including the start tempo: the last step may be
+    /// shorter so that the ramp finishes on the end tempo
+    fn step_count(&self) -> i32 {
+        ((self.end_tempo - self.start_tempo).abs() / self.step).ceil() as i32 + 1
+    }
+
+    fn tempo_at_step(&self, step: i32) -> f64 {
+        let delta = step as f64 * self.step;
+        if self.end_tempo >= self.start_tempo {
+            (self.start_tempo + delta).min(self.end_tempo)
+        } else {
+            (self.start_tempo - delta).max(self.end_tempo)
+        }
+    }
+}
+
+/// Tempo parameter in crotchets per minute
+pub fn new_tempo_param(name: &str, default: f32) -> FloatParam {
+    FloatParam::new(
+        name,
+        default,
+        FloatRange::Linear {
+            min: 20f32,
+            max: 400f32,
+        },
+    )
+    .with_unit(" qpm")
+    .with_step_size(0.1f32)
+}